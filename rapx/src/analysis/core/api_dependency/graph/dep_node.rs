@@ -1,12 +1,15 @@
+use std::fmt;
 use std::hash::Hash;
 
 use super::ty_wrapper::TyWrapper;
+use super::utils;
 use rustc_middle::{
     query::IntoQueryParam,
-    ty::{self, Ty, TyCtxt},
+    ty::{self, print::with_no_trimmed_paths, Ty, TyCtxt},
 };
 
 use rustc_hir::def_id::DefId;
+use rustc_span::Span;
 
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 enum IntrinsicKind {
@@ -26,7 +29,49 @@ pub fn desc_str<'tcx>(node: DepNode<'tcx>, tcx: TyCtxt<'tcx>) -> String {
     }
 }
 
+/// Display a `DepNode` in Rust syntax. An API is printed as a cast of its path to the
+/// instantiated fn pointer type, e.g., `Foo::new::<u8> as fn(u8, &str) -> Foo`, and a type
+/// node as its type. Unlike `desc_str`, paths are not trimmed and regions are erased.
+/// Local paths are relative to the crate root without `crate::`, so the output is meant
+/// for reading rather than for generating code.
+pub struct DepNodeDisplay<'tcx> {
+    pub node: DepNode<'tcx>,
+    pub tcx: TyCtxt<'tcx>,
+}
+
+impl<'tcx> fmt::Display for DepNodeDisplay<'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tcx = self.tcx;
+        with_no_trimmed_paths!(match self.node {
+            DepNode::Api(def_id, args) => {
+                let fn_sig = tcx.erase_and_anonymize_regions(utils::fn_sig_with_generic_args(
+                    def_id, args, tcx,
+                ));
+                write!(
+                    f,
+                    "{} as {}",
+                    tcx.def_path_str_with_args(def_id, args),
+                    Ty::new_fn_ptr(tcx, ty::Binder::dummy(fn_sig))
+                )
+            }
+            DepNode::Ty(ty) => write!(f, "{}", tcx.erase_and_anonymize_regions(ty.ty())),
+        })
+    }
+}
+
 impl<'tcx> DepNode<'tcx> {
+    /// Return the span of the API definition. Type nodes have no span.
+    pub fn span(&self, tcx: TyCtxt<'tcx>) -> Option<Span> {
+        match self {
+            DepNode::Api(def_id, _) => Some(tcx.def_span(*def_id)),
+            DepNode::Ty(_) => None,
+        }
+    }
+
+    pub fn display(self, tcx: TyCtxt<'tcx>) -> DepNodeDisplay<'tcx> {
+        DepNodeDisplay { node: self, tcx }
+    }
+
     pub fn api(id: impl IntoQueryParam<DefId>, args: ty::GenericArgsRef<'tcx>) -> DepNode<'tcx> {
         DepNode::Api(id.into_query_param(), args)
     }
@@ -0,0 +1,170 @@
+use super::dep_edge::DepEdge;
+use super::dep_node::DepNode;
+use super::utils;
+use super::ApiDependencyGraph;
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use rustc_middle::ty::{self, Ty};
+use std::collections::{HashMap, HashSet};
+
+/// A strongly connected component of the type dependency graph.
+/// A component is cyclic if some of its types can only be constructed from each other,
+/// e.g., `A` is only returned by an API taking `B`, and `B` is only returned by an API
+/// taking `A`. A component whose types also have producers taking inputs from earlier
+/// layers, e.g., a constructor besides `fn clone(&Foo) -> Foo`, is not cyclic.
+#[derive(Debug, Clone)]
+pub struct TyComponent<'tcx> {
+    pub tys: Vec<Ty<'tcx>>,
+    pub is_cyclic: bool,
+}
+
+impl<'tcx> ApiDependencyGraph<'tcx> {
+    /// Build the type dependency graph, whose nodes are the type nodes of this graph.
+    /// There is an edge `B -> A` if an API returning `A` takes `B` (or `&B`) as a non-fuzzable
+    /// argument, i.e., a value of `B` may be needed to construct `A`.
+    fn ty_dependency_graph(&self) -> DiGraph<NodeIndex, ()> {
+        let mut dep_graph = DiGraph::new();
+        let mut index_map = HashMap::new();
+        for index in self.ty_nodes.iter() {
+            index_map.insert(*index, dep_graph.add_node(*index));
+        }
+
+        for api in self.api_nodes.iter() {
+            let outputs: Vec<NodeIndex> = self
+                .graph
+                .edges_directed(*api, Direction::Outgoing)
+                .filter(|edge| matches!(edge.weight(), DepEdge::Ret))
+                .map(|edge| index_map[&edge.target()])
+                .collect();
+
+            for edge in self.graph.edges_directed(*api, Direction::Incoming) {
+                let input_ty = self.graph[edge.source()].expect_ty().ty();
+                // fuzzable arguments are provided directly
+                if !matches!(edge.weight(), DepEdge::Arg(_))
+                    || utils::is_fuzzable_ty(input_ty, self.tcx)
+                {
+                    continue;
+                }
+                let mut inputs = vec![index_map[&edge.source()]];
+                if let ty::Ref(_, inner_ty, _) = input_ty.kind() {
+                    if let Some(inner) = self.get_index(DepNode::ty(*inner_ty)) {
+                        inputs.push(index_map[&inner]);
+                    }
+                }
+                for input in inputs {
+                    for output in outputs.iter() {
+                        dep_graph.update_edge(input, *output, ());
+                    }
+                }
+            }
+        }
+        dep_graph
+    }
+
+    /// Group the type nodes into dependency layers. Types in layer `n` only depend on
+    /// types in the layers before `n`, so constructing types layer by layer never loops.
+    /// Mutually dependent types are put into one component, see `TyComponent` for when
+    /// it is cyclic.
+    pub fn topological_layers(&self) -> Vec<Vec<TyComponent<'tcx>>> {
+        let dep_graph = self.ty_dependency_graph();
+        // `tarjan_scc` returns the components in reverse topological order
+        let mut sccs = tarjan_scc(&dep_graph);
+        sccs.reverse();
+
+        let mut component_of = HashMap::new();
+        for (no, scc) in sccs.iter().enumerate() {
+            for node in scc {
+                component_of.insert(*node, no);
+            }
+        }
+
+        let mut layer_of = vec![0; sccs.len()];
+        let mut layers: Vec<Vec<TyComponent<'tcx>>> = Vec::new();
+        for (no, scc) in sccs.iter().enumerate() {
+            let mut has_inner_dependency = scc.len() > 1;
+            for node in scc {
+                for pred in dep_graph.neighbors_directed(*node, Direction::Incoming) {
+                    let pred_no = component_of[&pred];
+                    if pred_no == no {
+                        has_inner_dependency = true;
+                    } else {
+                        layer_of[no] = layer_of[no].max(layer_of[pred_no] + 1);
+                    }
+                }
+            }
+            let tys: HashSet<NodeIndex> = scc.iter().map(|node| dep_graph[*node]).collect();
+            let is_cyclic = has_inner_dependency && !self.is_constructible_from_outside(&tys);
+
+            let layer = layer_of[no];
+            if layers.len() <= layer {
+                layers.resize_with(layer + 1, Vec::new);
+            }
+            layers[layer].push(TyComponent {
+                tys: scc
+                    .iter()
+                    .map(|node| self.graph[dep_graph[*node]].expect_ty().ty())
+                    .collect(),
+                is_cyclic,
+            });
+        }
+        layers
+    }
+
+    /// Return true if all types of the component `tys` can be constructed, assuming that
+    /// the types out of the component are available. Starting from the producers whose
+    /// non-fuzzable inputs are all out of the component, types are constructed until no
+    /// more types of the component can be constructed.
+    fn is_constructible_from_outside(&self, tys: &HashSet<NodeIndex>) -> bool {
+        // a reference to a type of the component is obtained from that type,
+        // unless some API returns the reference directly
+        let inner_of = |input: NodeIndex| match self.graph[input].expect_ty().ty().kind() {
+            ty::Ref(_, inner_ty, _) => self.get_index(DepNode::ty(*inner_ty)),
+            _ => None,
+        };
+        let is_available = |input: NodeIndex, constructed: &HashSet<NodeIndex>| {
+            let input_ty = self.graph[input].expect_ty().ty();
+            if utils::is_fuzzable_ty(input_ty, self.tcx) || constructed.contains(&input) {
+                return true;
+            }
+            if tys.contains(&input) {
+                return false;
+            }
+            match inner_of(input) {
+                Some(inner) if tys.contains(&inner) => {
+                    constructed.contains(&inner) || !self.producers_of_index(input).is_empty()
+                }
+                _ => true,
+            }
+        };
+
+        let mut constructed = HashSet::new();
+        loop {
+            let newly_constructed: Vec<NodeIndex> = tys
+                .iter()
+                .filter(|ty| !constructed.contains(*ty))
+                .filter(|ty| {
+                    self.producers_of_index(**ty).into_iter().any(|api| {
+                        self.inputs_of(api)
+                            .into_iter()
+                            .all(|input| is_available(input, &constructed))
+                    })
+                })
+                .copied()
+                .collect();
+            if newly_constructed.is_empty() {
+                return constructed.len() == tys.len();
+            }
+            constructed.extend(newly_constructed);
+        }
+    }
+
+    /// Return true if some types in the graph can only be constructed from each other.
+    pub fn has_type_cycle(&self) -> bool {
+        self.topological_layers()
+            .iter()
+            .flatten()
+            .any(|component| component.is_cyclic)
+    }
+}
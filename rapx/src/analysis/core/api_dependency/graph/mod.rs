@@ -1,20 +1,23 @@
 pub mod avail;
 pub mod dep_edge;
 pub mod dep_node;
+mod layer;
 mod resolve;
 mod serialize;
+mod synthesis;
 pub mod transform;
 mod ty_wrapper;
 
 use super::utils;
 use super::visitor::FnVisitor;
-use super::Config;
+use super::{Config, Visibility};
 use crate::analysis::utils::def_path::path_str_def_id;
 use crate::rap_debug;
 use crate::rap_trace;
 use crate::utils::fs::rap_create_file;
 pub use dep_edge::DepEdge;
-pub use dep_node::{desc_str, DepNode};
+pub use dep_node::{desc_str, DepNode, DepNodeDisplay};
+pub use layer::TyComponent;
 use petgraph::dot;
 use petgraph::graph::NodeIndex;
 use petgraph::visit::EdgeRef;
@@ -40,6 +43,8 @@ pub struct ApiDependencyGraph<'tcx> {
     node_indices: HashMap<DepNode<'tcx>, NodeIndex>,
     ty_nodes: Vec<NodeIndex>,
     api_nodes: Vec<NodeIndex>,
+    /// the type as first written in a signature, for each type node keyed by canonical type
+    ty_labels: HashMap<DepNode<'tcx>, String>,
     all_apis: HashSet<DefId>,
     tcx: TyCtxt<'tcx>,
 }
@@ -47,7 +52,38 @@ pub struct ApiDependencyGraph<'tcx> {
 pub struct Statistics {
     pub api_count: usize,
     pub type_count: usize,
+    /// number of type nodes that are generic parameters, e.g., `T`
+    pub param_type_count: usize,
     pub edge_cnt: usize,
+    pub arg_edge_count: usize,
+    pub ret_edge_count: usize,
+    pub transform_edge_count: usize,
+    /// the most connected types, as (type, in-degree, out-degree), sorted by total degree.
+    /// The in-degree counts the APIs and transforms producing the type, and the
+    /// out-degree counts the APIs and transforms consuming it.
+    pub hub_types: Vec<(String, usize, usize)>,
+}
+
+impl std::fmt::Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "API Graph Statistics:")?;
+        writeln!(f, "  API nodes: {}", self.api_count)?;
+        writeln!(
+            f,
+            "  type nodes: {} ({} generic parameters)",
+            self.type_count, self.param_type_count
+        )?;
+        writeln!(
+            f,
+            "  edges: {} (arg: {}, ret: {}, transform: {})",
+            self.edge_cnt, self.arg_edge_count, self.ret_edge_count, self.transform_edge_count
+        )?;
+        writeln!(f, "  hub types (in-degree, out-degree):")?;
+        for (ty, in_degree, out_degree) in &self.hub_types {
+            writeln!(f, "    {}: ({}, {})", ty, in_degree, out_degree)?;
+        }
+        Ok(())
+    }
 }
 
 impl<'tcx> ApiDependencyGraph<'tcx> {
@@ -57,11 +93,21 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
             node_indices: HashMap::new(),
             ty_nodes: Vec::new(),
             api_nodes: Vec::new(),
+            ty_labels: HashMap::new(),
             tcx,
             all_apis: HashSet::new(),
         }
     }
 
+    /// Build the graph for the functions of the local crate with the given visibility.
+    /// Generic APIs are resolved to monomorphic ones, and the arg, ret and transform
+    /// edges are added from their signatures.
+    pub fn build_for_crate(tcx: TyCtxt<'tcx>, visibility: Visibility) -> ApiDependencyGraph<'tcx> {
+        let mut graph = ApiDependencyGraph::new(tcx);
+        graph.build(Config::for_visibility(visibility));
+        graph
+    }
+
     pub fn num_api(&self) -> usize {
         self.api_nodes.len()
     }
@@ -81,7 +127,7 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
 
     pub fn build(&mut self, config: Config) {
         let tcx = self.tcx();
-        let mut fn_visitor = FnVisitor::new(self, config, tcx);
+        let mut fn_visitor = FnVisitor::new(self, config.clone(), tcx);
 
         // 1. collect APIs
         tcx.hir_visit_all_item_likes_in_crate(&mut fn_visitor);
@@ -102,33 +148,79 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
     }
 
     pub fn statistics(&self) -> Statistics {
+        const MAX_HUB_TYPES: usize = 5;
         let mut api_cnt = 0;
         let mut ty_cnt = 0;
-        let mut edge_cnt = 0;
+        let mut param_ty_cnt = 0;
+        let mut hub_types = Vec::new();
 
         for node in self.graph.node_indices() {
             match self.graph[node] {
                 DepNode::Api(..) => api_cnt += 1,
-                DepNode::Ty(_) => ty_cnt += 1,
+                DepNode::Ty(ty) => {
+                    ty_cnt += 1;
+                    if matches!(ty.ty().kind(), ty::Param(_)) {
+                        param_ty_cnt += 1;
+                    }
+                    let in_degree = self.graph.edges_directed(node, Direction::Incoming).count();
+                    let out_degree = self.graph.edges_directed(node, Direction::Outgoing).count();
+                    hub_types.push((ty.desc_str(self.tcx), in_degree, out_degree));
+                }
             }
         }
-
-        for edge in self.graph.edge_indices() {
-            edge_cnt += 1;
+        hub_types.sort_by(|(lhs_ty, lhs_in, lhs_out), (rhs_ty, rhs_in, rhs_out)| {
+            (rhs_in + rhs_out)
+                .cmp(&(lhs_in + lhs_out))
+                .then_with(|| lhs_ty.cmp(rhs_ty))
+        });
+        hub_types.truncate(MAX_HUB_TYPES);
+
+        let (mut arg_cnt, mut ret_cnt, mut transform_cnt) = (0, 0, 0);
+        for edge in self.graph.edge_weights() {
+            match edge {
+                DepEdge::Arg(_) => arg_cnt += 1,
+                DepEdge::Ret => ret_cnt += 1,
+                DepEdge::Transform(_) => transform_cnt += 1,
+            }
         }
 
         Statistics {
             api_count: api_cnt,
             type_count: ty_cnt,
-            edge_cnt,
+            param_type_count: param_ty_cnt,
+            edge_cnt: self.graph.edge_count(),
+            arg_edge_count: arg_cnt,
+            ret_edge_count: ret_cnt,
+            transform_edge_count: transform_cnt,
+            hub_types,
+        }
+    }
+
+    /// Return the canonical form of `ty`: regions are erased and anonymized, and aliases
+    /// are normalized when possible. Types that only differ in regions or in the way they
+    /// are written, e.g., `&'a Foo` and `&'b Foo`, have the same canonical form.
+    pub fn canonical_ty(&self, ty: Ty<'tcx>) -> Ty<'tcx> {
+        let ty = self.tcx.erase_and_anonymize_regions(ty);
+        self.tcx
+            .try_normalize_erasing_regions(ty::TypingEnv::fully_monomorphized(), ty)
+            .unwrap_or(ty)
+    }
+
+    /// Type nodes are keyed by their canonical type, see `canonical_ty`.
+    fn canonical_node(&self, node: DepNode<'tcx>) -> DepNode<'tcx> {
+        match node {
+            DepNode::Ty(ty) => DepNode::ty(self.canonical_ty(ty.ty())),
+            _ => node,
         }
     }
 
     pub fn is_node_exist(&self, node: &DepNode<'tcx>) -> bool {
-        self.node_indices.contains_key(&node)
+        self.node_indices.contains_key(&self.canonical_node(*node))
     }
 
     pub fn get_or_create_index(&mut self, node: DepNode<'tcx>) -> NodeIndex {
+        let original = node;
+        let node = self.canonical_node(node);
         if let Some(node_index) = self.node_indices.get(&node) {
             *node_index
         } else {
@@ -140,6 +232,8 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
                 }
                 DepNode::Ty(_) => {
                     self.ty_nodes.push(node_index);
+                    self.ty_labels
+                        .insert(node, original.expect_ty().ty().to_string());
                 }
                 _ => {}
             }
@@ -148,7 +242,9 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
     }
 
     pub fn get_index(&self, node: DepNode<'tcx>) -> Option<NodeIndex> {
-        self.node_indices.get(&node).map(|index| *index)
+        self.node_indices
+            .get(&self.canonical_node(node))
+            .map(|index| *index)
     }
 
     pub fn add_edge(&mut self, src: NodeIndex, dst: NodeIndex, edge: DepEdge) {
@@ -199,6 +295,14 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
         true
     }
 
+    /// Return the argument numbers of `api` that its return value borrows from.
+    /// A driver calling `api` must keep these arguments alive as long as the returned value.
+    /// Regions are erased in type nodes, so this is computed from the declared signature.
+    pub fn return_borrows_from(&self, api: NodeIndex) -> Vec<usize> {
+        let (fn_did, _) = self.graph[api].expect_api();
+        utils::return_borrows_from(fn_did, self.tcx)
+    }
+
     /// return all transform kind for `ty` that we intersted in.
     pub fn all_transforms(&self, ty: Ty<'tcx>) -> Vec<TransformKind> {
         let mut tfs = Vec::new();
@@ -335,8 +439,9 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
                 + ", shape=box"
         };
 
+        let graph = self.canonical_graph();
         let dot = dot::Dot::with_attr_getters(
-            &self.graph,
+            &graph,
             &[dot::Config::NodeNoLabel, dot::Config::EdgeNoLabel],
             &get_edge_attr,
             &get_node_attr,
@@ -7,12 +7,12 @@ use crate::analysis::core::api_dependency::mono::Mono;
 use crate::analysis::core::api_dependency::utils::{is_fuzzable_ty, ty_complexity};
 use crate::analysis::core::api_dependency::visitor::FnVisitor;
 use crate::analysis::core::api_dependency::ApiDependencyGraph;
-use crate::analysis::core::api_dependency::{mono, utils};
+use crate::analysis::core::api_dependency::{is_provided_trait_method, mono, utils};
 use crate::utils::fs::rap_create_file;
-use crate::{rap_debug, rap_info, rap_trace};
+use crate::{rap_debug, rap_info, rap_trace, rap_warn};
 use petgraph::dot;
 use petgraph::graph::NodeIndex;
-use petgraph::visit::{NodeIndexable, Visitable};
+use petgraph::visit::{EdgeRef, NodeIndexable, Visitable};
 use petgraph::Direction::{self, Incoming};
 use petgraph::Graph;
 use rand::Rng;
@@ -168,6 +168,10 @@ pub fn partion_generic_api<'tcx>(
     let mut generic_api = HashSet::new();
     let mut non_generic_api = HashSet::new();
     for api_id in all_apis.iter() {
+        // provided trait methods are instantiated by the local impls when collected
+        if is_provided_trait_method(*api_id, tcx) {
+            continue;
+        }
         if tcx.generics_of(*api_id).requires_monomorphization(tcx) {
             generic_api.insert(*api_id);
         } else {
@@ -306,10 +310,13 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
         let mut reserved = vec![false; self.graph.node_count()];
 
         // initialize reserved
-        // all non-generic API should be reserved
+        // all non-generic API should be reserved, and so are the generic API not resolved
+        // by generic resolution, i.e., provided trait methods instantiated by local impls
         for idx in self.graph.node_indices() {
             if let DepNode::Api(fn_did, _) = self.graph[idx] {
-                if !utils::fn_requires_monomorphization(fn_did, self.tcx) {
+                if !utils::fn_requires_monomorphization(fn_did, self.tcx)
+                    || !reserved_map.contains_key(&fn_did)
+                {
                     reserved[idx.index()] = true;
                 }
             }
@@ -362,6 +369,91 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
         );
     }
 
+    /// Keep only the nodes reachable from the API nodes `roots`: the roots, the nodes reachable
+    /// along the edges from them, the inputs of every kept API, which are needed to call it,
+    /// and the producers of the inputs, i.e., the sources of their `Ret` and transform edges.
+    /// Node indices are invalidated by the removal, so `node_indices` and the node lists are
+    /// rebuilt afterwards. Return the removed nodes.
+    pub fn prune_unreachable(&mut self, roots: &[NodeIndex]) -> Vec<DepNode<'tcx>> {
+        let mut reachable = vec![false; self.graph.node_count()];
+        let mut produced = vec![false; self.graph.node_count()];
+        // each node is paired with whether it is an input of a kept API
+        let mut worklist: VecDeque<(NodeIndex, bool)> =
+            roots.iter().map(|root| (*root, false)).collect();
+        while let Some((node, is_input)) = worklist.pop_front() {
+            if is_input && !produced[node.index()] {
+                produced[node.index()] = true;
+                for edge in self.graph.edges_directed(node, Incoming) {
+                    match edge.weight() {
+                        DepEdge::Ret => worklist.push_back((edge.source(), false)),
+                        DepEdge::Transform(_) => worklist.push_back((edge.source(), true)),
+                        DepEdge::Arg(_) => {}
+                    }
+                }
+            }
+            if reachable[node.index()] {
+                continue;
+            }
+            reachable[node.index()] = true;
+            worklist.extend(
+                self.graph
+                    .neighbors_directed(node, Direction::Outgoing)
+                    .map(|next| (next, false)),
+            );
+            if self.graph[node].is_api() {
+                worklist.extend(
+                    self.graph
+                        .neighbors_directed(node, Incoming)
+                        .map(|input| (input, true)),
+                );
+            }
+        }
+
+        let mut removed = Vec::new();
+        // remove from the back, so that the nodes moved into the removed slots are all kept
+        for idx in (0..self.graph.node_count()).rev() {
+            if !reachable[idx] {
+                removed.push(
+                    self.graph
+                        .remove_node(NodeIndex::new(idx))
+                        .expect("remove should not fail"),
+                );
+            }
+        }
+        self.recache();
+        rap_debug!(
+            "remove {} nodes unreachable from {} roots",
+            removed.len(),
+            roots.len()
+        );
+        removed
+    }
+
+    /// Check that `get_index` returns the position of every node in the graph, and `None`
+    /// for the `removed` nodes. Return the number of nodes violating this.
+    pub fn check_indices(&self, removed: &[DepNode<'tcx>]) -> usize {
+        let mut inconsistent = 0;
+        for idx in self.graph.node_indices() {
+            let index = self.get_index(self.graph[idx]);
+            if index != Some(idx) {
+                rap_warn!(
+                    "{:?} at {:?} is indexed as {:?}",
+                    self.graph[idx],
+                    idx,
+                    index
+                );
+                inconsistent += 1;
+            }
+        }
+        for node in removed {
+            if let Some(index) = self.get_index(*node) {
+                rap_warn!("removed {:?} is still indexed as {:?}", node, index);
+                inconsistent += 1;
+            }
+        }
+        inconsistent
+    }
+
     fn recache(&mut self) {
         self.node_indices.clear();
         self.ty_nodes.clear();
@@ -375,6 +467,8 @@ impl<'tcx> ApiDependencyGraph<'tcx> {
                 _ => {}
             }
         }
+        self.ty_labels
+            .retain(|node, _| self.node_indices.contains_key(node));
     }
 
     pub fn propagate_reserved(
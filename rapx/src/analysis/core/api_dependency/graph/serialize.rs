@@ -1,18 +1,30 @@
 use super::dep_edge::DepEdge;
-use super::dep_node::DepNode;
+use super::dep_node::{desc_str, DepNode};
+use super::utils;
+use super::InnerGraph;
 use crate::analysis::core::api_dependency::ApiDependencyGraph;
+use petgraph::graph::NodeIndex;
+use petgraph::Graph;
 use serde::{
     ser::{SerializeMap, SerializeSeq},
     Serialize,
 };
+use std::collections::HashMap;
 use std::path::Path;
 
 #[derive(Serialize, Debug)]
 struct NodeInfo {
     id: usize,
     kind: String,
+    /// path of the API, or the rendered type of a type node
     path: String,
+    /// stable identifier of the API, i.e., `crate_name[hash]::path`
+    def_id: Option<String>,
+    /// the type of a type node as first written in a signature, before normalization
+    label: Option<String>,
     args: Vec<String>,
+    /// arguments that the return value of the API borrows from
+    borrows_from: Vec<usize>,
 }
 
 #[derive(Serialize, Debug)]
@@ -23,7 +35,62 @@ struct EdgeInfo {
     to: usize,
 }
 
+fn edge_kind_str(edge: &DepEdge) -> String {
+    match edge {
+        DepEdge::Arg(no) => format!("arg({})", no),
+        DepEdge::Ret => "ret".to_owned(),
+        DepEdge::Transform(kind) => format!("transform({})", kind),
+    }
+}
+
 impl<'tcx> ApiDependencyGraph<'tcx> {
+    /// Return a copy of the graph whose node and edge indices do not depend on the
+    /// construction order. Nodes are ordered by kind (APIs first) and description,
+    /// edges by their endpoints and kind. Both the DOT and the JSON writers use this
+    /// graph, so the ids in both outputs are stable across runs and consistent with each other.
+    pub(super) fn canonical_graph(&self) -> InnerGraph<'tcx> {
+        let mut nodes: Vec<(NodeIndex, bool, String)> = self
+            .graph
+            .node_indices()
+            .map(|index| {
+                let node = self.graph[index];
+                (index, node.is_ty(), desc_str(node, self.tcx))
+            })
+            .collect();
+        nodes.sort_by(
+            |(lhs_idx, lhs_is_ty, lhs_desc), (rhs_idx, rhs_is_ty, rhs_desc)| {
+                (lhs_is_ty, lhs_desc, lhs_idx).cmp(&(rhs_is_ty, rhs_desc, rhs_idx))
+            },
+        );
+
+        let mut graph = Graph::new();
+        let mut index_map = HashMap::new();
+        for (old_index, _, _) in nodes {
+            let new_index = graph.add_node(self.graph[old_index]);
+            index_map.insert(old_index, new_index);
+        }
+
+        let mut edges: Vec<(usize, usize, String, DepEdge)> = self
+            .graph
+            .edge_indices()
+            .map(|index| {
+                let (from, to) = self.graph.edge_endpoints(index).unwrap();
+                let edge = self.graph[index];
+                (
+                    index_map[&from].index(),
+                    index_map[&to].index(),
+                    edge_kind_str(&edge),
+                    edge,
+                )
+            })
+            .collect();
+        edges.sort_by(|lhs, rhs| (lhs.0, lhs.1, &lhs.2).cmp(&(rhs.0, rhs.1, &rhs.2)));
+        for (from, to, _, edge) in edges {
+            graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), edge);
+        }
+        graph
+    }
+
     pub fn dump_to_json(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
         let file = std::fs::File::create(path)?;
         serde_json::to_writer_pretty(file, self)?;
@@ -36,39 +103,40 @@ impl<'tcx> Serialize for ApiDependencyGraph<'tcx> {
     where
         S: serde::Serializer,
     {
+        let graph = self.canonical_graph();
         let mut map = serializer.serialize_map(Some(2))?;
         let mut nodes = Vec::new();
-        for index in self.graph.node_indices() {
-            let node_info = match self.graph[index] {
+        for index in graph.node_indices() {
+            let node_info = match graph[index] {
                 DepNode::Api(fn_did, args) => NodeInfo {
                     id: index.index(),
                     kind: "api".to_owned(),
                     path: self.tcx.def_path_str(fn_did),
+                    def_id: Some(self.tcx.def_path_debug_str(fn_did)),
+                    label: None,
                     args: args.iter().map(|arg| arg.to_string()).collect(),
+                    borrows_from: utils::return_borrows_from(fn_did, self.tcx),
                 },
                 DepNode::Ty(ty) => NodeInfo {
                     id: index.index(),
                     kind: "type".to_owned(),
                     path: ty.ty().to_string(),
+                    def_id: None,
+                    label: self.ty_labels.get(&graph[index]).cloned(),
                     args: vec![],
+                    borrows_from: vec![],
                 },
             };
             nodes.push(node_info);
         }
         let mut edges = Vec::new();
-        for index in self.graph.edge_indices() {
-            let kind = match self.graph[index] {
-                DepEdge::Arg(no) => "arg".to_owned(),
-                DepEdge::Ret => "ret".to_owned(),
-                DepEdge::Transform(kind) => format!("transform({})", kind),
-            };
-            let (from, to) = self.graph.edge_endpoints(index).unwrap();
-            let (from, to) = (from.index(), to.index());
+        for index in graph.edge_indices() {
+            let (from, to) = graph.edge_endpoints(index).unwrap();
             edges.push(EdgeInfo {
                 id: index.index(),
-                kind: "arg".to_owned(),
-                from,
-                to,
+                kind: edge_kind_str(&graph[index]),
+                from: from.index(),
+                to: to.index(),
             });
         }
         map.serialize_entry("nodes", &nodes)?;
@@ -0,0 +1,177 @@
+use super::dep_edge::DepEdge;
+use super::dep_node::{desc_str, DepNode};
+use super::utils;
+use super::ApiDependencyGraph;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use rustc_middle::ty::{self, Ty};
+use std::collections::HashMap;
+
+impl<'tcx> ApiDependencyGraph<'tcx> {
+    /// Return all API nodes whose return value has type `ty`.
+    pub fn producers_of(&self, ty: Ty<'tcx>) -> Vec<NodeIndex> {
+        match self.get_index(DepNode::ty(ty)) {
+            Some(index) => self.producers_of_index(index),
+            None => Vec::new(),
+        }
+    }
+
+    pub(super) fn producers_of_index(&self, ty_index: NodeIndex) -> Vec<NodeIndex> {
+        let mut producers: Vec<NodeIndex> = self
+            .graph
+            .edges_directed(ty_index, Direction::Incoming)
+            .filter(|edge| matches!(edge.weight(), DepEdge::Ret))
+            .map(|edge| edge.source())
+            .collect();
+        producers.sort();
+        producers.dedup();
+        producers
+    }
+
+    /// Return the input type nodes of `api_index`, ordered by argument number.
+    pub(super) fn inputs_of(&self, api_index: NodeIndex) -> Vec<NodeIndex> {
+        let mut inputs: Vec<(usize, NodeIndex)> = self
+            .graph
+            .edges_directed(api_index, Direction::Incoming)
+            .filter_map(|edge| match edge.weight() {
+                DepEdge::Arg(no) => Some((*no, edge.source())),
+                _ => None,
+            })
+            .collect();
+        inputs.sort();
+        inputs.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Return candidate API call sequences that produce a value of `target_ty`, one for each
+    /// API returning `target_ty`.
+    /// Each sequence is ordered by execution: the APIs constructing the arguments come first
+    /// and the API returning `target_ty` is the last one. Fuzzable arguments are assumed to be
+    /// provided directly. `&T` and `&mut T` arguments can be obtained from a constructed `T`.
+    /// Each argument is constructed by its shortest sequence, and the search stops at
+    /// `max_depth` nested producers. The result is sorted by sequence length.
+    pub fn synthesis_paths(
+        &self,
+        target_ty: Ty<'tcx>,
+        max_depth: usize,
+    ) -> Vec<Vec<DepNode<'tcx>>> {
+        let Some(index) = self.get_index(DepNode::ty(target_ty)) else {
+            return Vec::new();
+        };
+        if max_depth == 0 {
+            return Vec::new();
+        }
+
+        let mut memo = HashMap::new();
+        let mut paths = Vec::new();
+        for producer in self.producers_of_index(index) {
+            if let Some(mut path) = self.construct_inputs(producer, max_depth, &mut memo) {
+                path.push(self.graph[producer]);
+                paths.push(path);
+            }
+        }
+        paths.sort_by_key(|path| path.len());
+        paths
+    }
+
+    /// Return the shortest API sequence that produces a value of `ty_index` within `depth`
+    /// nested producers, or `None` if there is no such sequence.
+    /// Results are memoized per (type, depth), so each pair is only searched once.
+    /// A shortest sequence never constructs the same type twice, so no cycle check is needed.
+    fn shortest_path_for(
+        &self,
+        ty_index: NodeIndex,
+        depth: usize,
+        memo: &mut HashMap<(NodeIndex, usize), Option<Vec<DepNode<'tcx>>>>,
+    ) -> Option<Vec<DepNode<'tcx>>> {
+        if depth == 0 {
+            return None;
+        }
+        if let Some(path) = memo.get(&(ty_index, depth)) {
+            return path.clone();
+        }
+
+        let mut shortest: Option<Vec<DepNode<'tcx>>> = None;
+        for producer in self.producers_of_index(ty_index) {
+            if let Some(mut path) = self.construct_inputs(producer, depth, memo) {
+                path.push(self.graph[producer]);
+                if shortest
+                    .as_ref()
+                    .is_none_or(|shortest| path.len() < shortest.len())
+                {
+                    shortest = Some(path);
+                }
+            }
+        }
+        memo.insert((ty_index, depth), shortest.clone());
+        shortest
+    }
+
+    /// Return the shortest API sequence that constructs all inputs of `api_index`,
+    /// or `None` if some input cannot be constructed.
+    fn construct_inputs(
+        &self,
+        api_index: NodeIndex,
+        depth: usize,
+        memo: &mut HashMap<(NodeIndex, usize), Option<Vec<DepNode<'tcx>>>>,
+    ) -> Option<Vec<DepNode<'tcx>>> {
+        let mut path = Vec::new();
+        for input in self.inputs_of(api_index) {
+            let input_ty = self.graph[input].expect_ty().ty();
+            if utils::is_fuzzable_ty(input_ty, self.tcx) {
+                continue;
+            }
+
+            // a reference can be taken from a value of the referenced type
+            let mut candidates = vec![input];
+            if let ty::Ref(_, inner_ty, _) = input_ty.kind() {
+                if let Some(inner) = self.get_index(DepNode::ty(*inner_ty)) {
+                    candidates.push(inner);
+                }
+            }
+
+            let sub_path = candidates
+                .into_iter()
+                .filter_map(|candidate| self.shortest_path_for(candidate, depth - 1, memo))
+                .min_by_key(|sub_path| sub_path.len())?;
+            path.extend(sub_path);
+        }
+        Some(path)
+    }
+
+    /// Return the type nodes whose description or path matches `name`.
+    /// e.g., `Foo` matches both `Foo` and `my_crate::Foo`.
+    pub fn ty_nodes_named(&self, name: &str) -> Vec<Ty<'tcx>> {
+        let suffix = format!("::{}", name);
+        self.ty_nodes
+            .iter()
+            .map(|index| self.graph[*index].expect_ty())
+            .filter(|ty| {
+                let desc = ty.desc_str(self.tcx);
+                let ty_str = ty.ty().to_string();
+                desc == name || ty_str == name || ty_str.ends_with(&suffix)
+            })
+            .map(|ty| ty.ty())
+            .collect()
+    }
+
+    /// Return the API nodes whose path is one of `paths`, e.g., `Foo::new`.
+    pub fn api_nodes_named(&self, paths: &[String]) -> Vec<NodeIndex> {
+        self.api_nodes
+            .iter()
+            .copied()
+            .filter(|index| {
+                let (fn_did, _) = self.graph[*index].expect_api();
+                paths.contains(&self.tcx.def_path_str(fn_did))
+            })
+            .collect()
+    }
+
+    /// Format a synthesis path as `api1 -> api2 -> ...`.
+    pub fn synthesis_path_str(&self, path: &[DepNode<'tcx>]) -> String {
+        path.iter()
+            .map(|node| desc_str(*node, self.tcx))
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+}
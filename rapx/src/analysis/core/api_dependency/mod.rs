@@ -10,18 +10,95 @@ mod utils;
 mod visitor;
 
 use crate::analysis::Analysis;
-use crate::{rap_debug, rap_info, rap_trace};
+use crate::utils::log::{span_to_filename, span_to_line_number};
+use crate::utils::report::{self, Artifact};
+use crate::{rap_debug, rap_info, rap_trace, rap_warn};
 pub use graph::ApiDependencyGraph;
 pub use graph::{DepEdge, DepNode};
+use rustc_hir::def::DefKind;
 use rustc_hir::def_id::{DefId, LOCAL_CRATE};
 use rustc_middle::ty::TyCtxt;
 pub use utils::is_fuzzable_ty;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Default)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Default)]
 pub struct Config {
     pub pub_only: bool,
     pub resolve_generic: bool,
     pub ignore_const_generic: bool,
+    /// skip methods defined in trait impls, e.g., `impl Display for Foo { fn fmt(..) }`
+    pub skip_trait_impls: bool,
+    /// only include functions in one of the modules, i.e., whose def path or module path is
+    /// a prefix or starts with `prefix::`; an empty list includes all functions.
+    pub path_prefixes: Vec<String>,
+}
+
+/// Which functions of a crate are included in an API dependency graph.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Visibility {
+    /// only functions that are directly public
+    Public,
+    /// all functions
+    All,
+}
+
+impl Config {
+    /// The default configuration for the functions of the given visibility: generic APIs
+    /// are resolved, functions with const generics are skipped, and nothing is filtered.
+    pub fn for_visibility(visibility: Visibility) -> Config {
+        Config {
+            pub_only: visibility == Visibility::Public,
+            resolve_generic: true,
+            ignore_const_generic: true,
+            ..Default::default()
+        }
+    }
+
+    /// Return true if the function passes the trait impl and path prefix filters.
+    pub fn is_fn_included(&self, fn_did: DefId, tcx: TyCtxt<'_>) -> bool {
+        if self.skip_trait_impls && is_trait_impl_fn(fn_did, tcx) {
+            return false;
+        }
+        self.is_under_prefixes(fn_did, tcx)
+    }
+
+    /// Return true if the path or the enclosing module of `def_id` is under one of the path
+    /// prefixes. The module is needed for trait impl methods, whose def paths, e.g.,
+    /// `<colors::Rgb as Default>::default`, do not start with their modules.
+    pub fn is_under_prefixes(&self, def_id: DefId, tcx: TyCtxt<'_>) -> bool {
+        if self.path_prefixes.is_empty() {
+            return true;
+        }
+        let mut paths = vec![tcx.def_path_str(def_id)];
+        if let Some(local_id) = def_id.as_local() {
+            paths.push(tcx.def_path_str(tcx.parent_module_from_def_id(local_id).to_def_id()));
+        }
+        self.path_prefixes.iter().any(|prefix| {
+            paths
+                .iter()
+                .any(|path| path == prefix || path.starts_with(&format!("{prefix}::")))
+        })
+    }
+}
+
+pub fn is_trait_impl_fn(fn_did: DefId, tcx: TyCtxt<'_>) -> bool {
+    if tcx.def_kind(fn_did) != DefKind::AssocFn {
+        return false;
+    }
+    match tcx.opt_parent(fn_did) {
+        Some(parent) => matches!(tcx.def_kind(parent), DefKind::Impl { of_trait: true }),
+        None => false,
+    }
+}
+
+/// Return true if `fn_did` is a method with a default body defined in a trait.
+pub fn is_provided_trait_method(fn_did: DefId, tcx: TyCtxt<'_>) -> bool {
+    if tcx.def_kind(fn_did) != DefKind::AssocFn {
+        return false;
+    }
+    match tcx.opt_parent(fn_did) {
+        Some(parent) => tcx.def_kind(parent) == DefKind::Trait,
+        None => false,
+    }
 }
 
 pub fn is_def_id_public(fn_def_id: impl Into<DefId>, tcx: TyCtxt<'_>) -> bool {
@@ -45,6 +122,8 @@ pub struct ApiDependencyAnalyzer<'tcx> {
     tcx: TyCtxt<'tcx>,
     config: Config,
     api_graph: ApiDependencyGraph<'tcx>,
+    synthesis_target: Option<String>,
+    roots: Vec<String>,
 }
 
 impl<'tcx> ApiDependencyAnalyzer<'tcx> {
@@ -53,8 +132,21 @@ impl<'tcx> ApiDependencyAnalyzer<'tcx> {
             tcx,
             config,
             api_graph: ApiDependencyGraph::new(tcx),
+            synthesis_target: None,
+            roots: Vec::new(),
         }
     }
+
+    /// Print the API call sequences that construct the type named `ty_name` after the graph is built.
+    pub fn set_synthesis_target(&mut self, ty_name: impl ToString) {
+        self.synthesis_target = Some(ty_name.to_string());
+    }
+
+    /// Only keep the nodes reachable from the APIs with path `path` after the graph is built.
+    /// Can be called multiple times to keep the nodes reachable from any of them.
+    pub fn add_root(&mut self, path: impl ToString) {
+        self.roots.push(path.to_string());
+    }
 }
 
 impl<'tcx> Analysis for ApiDependencyAnalyzer<'tcx> {
@@ -65,7 +157,7 @@ impl<'tcx> Analysis for ApiDependencyAnalyzer<'tcx> {
     fn run(&mut self) {
         let local_crate_name = self.tcx.crate_name(LOCAL_CRATE);
         let local_crate_type = self.tcx.crate_types()[0];
-        let config = self.config;
+        let config = self.config.clone();
         rap_debug!(
             "Build API dependency graph on {} ({}), config = {:?}",
             local_crate_name.as_str(),
@@ -76,6 +168,25 @@ impl<'tcx> Analysis for ApiDependencyAnalyzer<'tcx> {
         let mut api_graph = ApiDependencyGraph::new(self.tcx);
         api_graph.build(config);
 
+        if !self.roots.is_empty() {
+            let roots = api_graph.api_nodes_named(&self.roots);
+            if roots.is_empty() {
+                rap_warn!("No API named {:?}, skip pruning", self.roots);
+            } else {
+                let removed = api_graph.prune_unreachable(&roots);
+                debug_assert_eq!(
+                    api_graph.check_indices(&removed),
+                    0,
+                    "inconsistent indices after pruning"
+                );
+                rap_info!(
+                    "Prune API graph from {} roots: {} nodes removed",
+                    roots.len(),
+                    removed.len()
+                );
+            }
+        }
+
         let (estimate, total) = api_graph.estimate_coverage();
 
         let statistics = api_graph.statistics();
@@ -92,14 +203,65 @@ impl<'tcx> Analysis for ApiDependencyAnalyzer<'tcx> {
             estimate,
             total
         );
+        let layers = api_graph.topological_layers();
+        let cyclic: Vec<_> = layers
+            .iter()
+            .flatten()
+            .filter(|component| component.is_cyclic)
+            .collect();
+        rap_info!(
+            "Type dependency: {} layers, {} cyclic components",
+            layers.len(),
+            cyclic.len()
+        );
+        for component in cyclic {
+            rap_debug!("mutually dependent types: {:?}", component.tys);
+        }
         let dot_path = format!("api_graph_{}_{}.dot", local_crate_name, local_crate_type);
         let json_path = format!("api_graph_{}_{}.json", local_crate_name, local_crate_type);
         rap_info!("Dump API dependency graph to {}", dot_path);
-        api_graph.dump_to_dot(dot_path, self.tcx);
+        api_graph.dump_to_dot(&dot_path, self.tcx);
         api_graph
             .dump_to_json(&json_path)
             .expect("failed to dump API graph to JSON");
         rap_info!("Dump API dependency graph to {}", json_path);
+        report::register(
+            "api_dependency",
+            "statistics",
+            Artifact::Json(serde_json::json!({
+                "api_count": statistics.api_count,
+                "type_count": statistics.type_count,
+                "edge_count": statistics.edge_cnt,
+                "estimated_coverage": [estimate, total],
+            })),
+        );
+        report::register("api_dependency", "dot", Artifact::File(dot_path.into()));
+        report::register("api_dependency", "json", Artifact::File(json_path.into()));
+
+        if let Some(ty_name) = &self.synthesis_target {
+            let max_depth = 5;
+            let max_paths = 10;
+            for ty in api_graph.ty_nodes_named(ty_name) {
+                let paths = api_graph.synthesis_paths(ty, max_depth);
+                rap_info!("{} synthesis path(s) for {}", paths.len(), ty);
+                for (no, path) in paths.iter().take(max_paths).enumerate() {
+                    rap_info!("#{}: {}", no, api_graph.synthesis_path_str(path));
+                    for node in path {
+                        let Some(span) = node.span(self.tcx) else {
+                            continue;
+                        };
+                        rap_info!(
+                            "    {} // {}:{}",
+                            node.display(self.tcx),
+                            span_to_filename(span),
+                            span_to_line_number(span)
+                        );
+                    }
+                }
+            }
+        }
+
+        self.api_graph = api_graph;
     }
 
     fn reset(&mut self) {
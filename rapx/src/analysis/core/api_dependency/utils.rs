@@ -2,6 +2,7 @@ use rustc_hir::def_id::DefId;
 use rustc_hir::LangItem;
 use rustc_middle::ty::{self, FnSig, Ty, TyCtxt, TyKind};
 use rustc_span::sym;
+use std::collections::HashSet;
 
 fn is_fuzzable_std_ty<'tcx>(ty: Ty<'tcx>, tcx: TyCtxt<'tcx>) -> bool {
     match ty.kind() {
@@ -90,6 +91,35 @@ pub fn fn_sig_with_generic_args<'tcx>(
     tcx.liberate_late_bound_regions(fn_did, binder_fn_sig)
 }
 
+/// Return the numbers of the arguments that the return value of `fn_did` may borrow from,
+/// i.e., the arguments sharing a non-static region with the return type.
+/// e.g., `fn get<'a>(x: &'a Foo, n: usize) -> &'a Bar` returns `[0]`.
+pub fn return_borrows_from<'tcx>(fn_did: DefId, tcx: TyCtxt<'tcx>) -> Vec<usize> {
+    let fn_sig = fn_sig_without_binders(fn_did, tcx);
+    let mut output_regions = HashSet::new();
+    tcx.for_each_free_region(&fn_sig.output(), |region| {
+        if !region.is_static() {
+            output_regions.insert(region);
+        }
+    });
+    if output_regions.is_empty() {
+        return Vec::new();
+    }
+
+    fn_sig
+        .inputs()
+        .iter()
+        .enumerate()
+        .filter_map(|(no, input_ty)| {
+            let mut borrowed = false;
+            tcx.for_each_free_region(input_ty, |region| {
+                borrowed |= output_regions.contains(&region);
+            });
+            borrowed.then_some(no)
+        })
+        .collect()
+}
+
 pub fn fn_requires_monomorphization<'tcx>(fn_did: DefId, tcx: TyCtxt<'_>) -> bool {
     tcx.generics_of(fn_did).requires_monomorphization(tcx)
 }
@@ -1,7 +1,7 @@
 use super::graph::ApiDependencyGraph;
 use super::graph::{DepEdge, DepNode};
-use super::is_def_id_public;
 use super::Config;
+use super::{is_def_id_public, is_provided_trait_method};
 use crate::analysis::core::api_dependency::mono;
 use crate::{rap_debug, rap_trace};
 use rustc_hir::{
@@ -49,6 +49,92 @@ impl<'tcx, 'a> FnVisitor<'tcx, 'a> {
             write!(f, "{}\n", self.tcx.def_path_str(id)).expect("fail when write funcs");
         }
     }
+
+    /// Return the trait if `fn_did` is a method with a default body defined in the trait.
+    fn provided_trait_method_of(&self, fn_did: DefId) -> Option<DefId> {
+        if is_provided_trait_method(fn_did, self.tcx) {
+            self.tcx.opt_parent(fn_did)
+        } else {
+            None
+        }
+    }
+
+    /// Return true if the impl passes the visibility and path prefix filters, i.e., its
+    /// module is included, and with `pub_only`, the local types in its self type are public.
+    fn is_impl_included(&self, impl_did: DefId, self_ty: Ty<'tcx>) -> bool {
+        if self.config.pub_only
+            && !self_ty
+                .walk()
+                .filter_map(|arg| arg.as_type())
+                .all(|ty| match ty.kind() {
+                    TyKind::Adt(adt_def, _) if adt_def.did().is_local() => {
+                        is_def_id_public(adt_def.did(), self.tcx)
+                    }
+                    _ => true,
+                })
+        {
+            return false;
+        }
+        self.config.is_under_prefixes(impl_did, self.tcx)
+    }
+
+    /// Add the provided method `fn_did` of `trait_did` for every non-generic local impl
+    /// that does not override it and passes the filters of the config. `Self` and the
+    /// trait parameters are substituted by the impl's trait ref. Generic impls and
+    /// generic methods are not instantiated.
+    fn add_provided_trait_method(&mut self, fn_did: DefId, trait_did: DefId) {
+        if self.config.skip_trait_impls
+            || self.tcx.generics_of(fn_did).own_requires_monomorphization()
+        {
+            return;
+        }
+        let mut added = false;
+        for impl_did in self.tcx.all_impls(trait_did) {
+            if !impl_did.is_local()
+                || self
+                    .tcx
+                    .impl_item_implementor_ids(impl_did)
+                    .contains_key(&fn_did)
+            {
+                continue;
+            }
+            if self
+                .tcx
+                .generics_of(impl_did)
+                .requires_monomorphization(self.tcx)
+            {
+                rap_debug!(
+                    "skip provided method {} for generic impl {}",
+                    self.tcx.def_path_str(fn_did),
+                    self.tcx.def_path_str(impl_did)
+                );
+                continue;
+            }
+            let trait_ref = self
+                .tcx
+                .impl_trait_ref(impl_did)
+                .unwrap()
+                .instantiate_identity();
+            if !self.is_impl_included(impl_did, trait_ref.self_ty()) {
+                continue;
+            }
+            let args = ty::GenericArgs::identity_for_item(self.tcx, fn_did).rebase_onto(
+                self.tcx,
+                trait_did,
+                trait_ref.args,
+            );
+            rap_trace!(
+                "add provided method {} for {}",
+                self.tcx.def_path_str(fn_did),
+                trait_ref.self_ty()
+            );
+            self.graph.add_api(fn_did, args);
+            added = true;
+        }
+        if added {
+            self.apis.push(fn_did);
+        }
+    }
 }
 
 pub fn has_const_generics(generics: &ty::Generics, tcx: TyCtxt<'_>) -> bool {
@@ -85,6 +171,20 @@ impl<'tcx, 'a> Visitor<'tcx> for FnVisitor<'tcx, 'a> {
             return;
         }
 
+        // a provided trait method has `Self` as a generic parameter,
+        // instantiate it with each implementing type of the local crate,
+        // which are filtered by impl instead of by the trait
+        if let Some(trait_did) = self.provided_trait_method_of(fn_did) {
+            self.add_provided_trait_method(fn_did, trait_did);
+            return;
+        }
+
+        // skip trait impl methods and functions out of the configured modules,
+        // so that their types are not added to the graph either
+        if !self.config.is_fn_included(fn_did, self.tcx) {
+            return;
+        }
+
         // if config.resolve_generic is false,
         // skip all generic functions
         if !self.config.resolve_generic && is_generic {
@@ -1,15 +1,21 @@
-use rustc_hir::{def::DefKind, def_id::DefId};
-use rustc_middle::{
-    mir::{self, Body},
-    ty::TyCtxt,
+use petgraph::{
+    algo::tarjan_scc,
+    graph::{DiGraph, NodeIndex},
+    Direction,
 };
-use std::collections::HashSet;
-use std::{collections::HashMap, hash::Hash};
+use rustc_hir::def_id::DefId;
+use rustc_middle::{mir, ty::TyCtxt};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::{collections::HashMap, fmt, hash::Hash};
 
 use super::visitor::CallGraphVisitor;
 use crate::{
     analysis::core::callgraph::{CallGraph, CallGraphAnalysis},
-    rap_debug, rap_info, Analysis,
+    analysis::utils::mir::MirAccess,
+    rap_debug, rap_info, rap_warn, Analysis,
 };
 
 pub struct CallGraphAnalyzer<'tcx> {
@@ -23,8 +29,7 @@ impl<'tcx> Analysis for CallGraphAnalyzer<'tcx> {
     }
 
     fn run(&mut self) {
-        let mut analysis = CallGraphAnalyzer::new(self.tcx);
-        analysis.start();
+        self.start();
     }
 
     fn reset(&mut self) {
@@ -73,35 +78,19 @@ impl<'tcx> CallGraphAnalyzer<'tcx> {
     }
 
     pub fn start(&mut self) {
+        let mut mir_access = MirAccess::new(self.tcx);
         for local_def_id in self.tcx.iter_local_def_id() {
-            if self.tcx.hir_maybe_body_owned_by(local_def_id).is_some() {
-                let def_id = local_def_id.to_def_id();
-                if self.tcx.is_mir_available(def_id) {
-                    let def_kind = self.tcx.def_kind(def_id);
-
-                    let body: &Body<'_> = match def_kind {
-                        DefKind::Fn | DefKind::AssocFn => &self.tcx.optimized_mir(def_id),
-                        DefKind::Const
-                        | DefKind::Static { .. }
-                        | DefKind::AssocConst
-                        | DefKind::InlineConst
-                        | DefKind::AnonConst => {
-                            // NOTE: safer fallback for constants
-                            &self.tcx.mir_for_ctfe(def_id)
-                        }
-                        // These don't have MIR or shouldn't be visited
-                        _ => {
-                            rap_debug!("Skipping def_id {:?} with kind {:?}", def_id, def_kind);
-                            continue;
-                        }
-                    };
-
-                    let mut call_graph_visitor =
-                        CallGraphVisitor::new(self.tcx, def_id.into(), body, &mut self.graph);
-                    call_graph_visitor.visit();
-                }
+            let def_id = local_def_id.to_def_id();
+            if let Some(body) = mir_access.body(def_id) {
+                let mut call_graph_visitor =
+                    CallGraphVisitor::new(self.tcx, def_id.into(), body, &mut self.graph);
+                call_graph_visitor.visit();
             }
         }
+        rap_debug!(
+            "CallGraph: skipped {} definitions without MIR",
+            mir_access.skipped().len()
+        );
     }
 
     pub fn get_callee_def_path(&self, def_path: String) -> Option<HashSet<String>> {
@@ -132,6 +121,43 @@ impl Node {
     }
 }
 
+/// Structural statistics of a call graph, mainly used to diagnose analysis performance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallGraphStats {
+    pub num_functions: usize,
+    /// Number of distinct caller-callee pairs.
+    pub num_edges: usize,
+    pub max_in_degree: usize,
+    pub max_out_degree: usize,
+    /// Number of strongly connected components with at least two functions.
+    pub num_recursive_sccs: usize,
+    /// Number of functions calling themselves directly.
+    pub num_self_recursive_fns: usize,
+    /// (SCC size, number of SCCs of that size), sorted by size.
+    pub strongly_connected_component_size_histogram: Vec<(usize, usize)>,
+}
+
+impl fmt::Display for CallGraphStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CallGraph Statistics:")?;
+        writeln!(f, "  functions: {}", self.num_functions)?;
+        writeln!(f, "  edges: {}", self.num_edges)?;
+        writeln!(f, "  max in-degree: {}", self.max_in_degree)?;
+        writeln!(f, "  max out-degree: {}", self.max_out_degree)?;
+        writeln!(f, "  recursive SCCs: {}", self.num_recursive_sccs)?;
+        writeln!(
+            f,
+            "  self-recursive functions: {}",
+            self.num_self_recursive_fns
+        )?;
+        writeln!(f, "  SCC size histogram (size: count):")?;
+        for (size, count) in &self.strongly_connected_component_size_histogram {
+            writeln!(f, "    {}: {}", size, count)?;
+        }
+        Ok(())
+    }
+}
+
 pub struct CallGraphInfo<'tcx> {
     pub functions: HashMap<usize, Node>, // id -> node
     pub fn_calls: HashMap<usize, Vec<(usize, &'tcx mir::Terminator<'tcx>)>>, // caller_id -> Vec<(callee_id, terminator)>
@@ -226,6 +252,159 @@ impl<'tcx> CallGraphInfo<'tcx> {
         }
     }
 
+    /// Build a petgraph view of the call graph, whose node weights are the function ids.
+    /// Parallel call edges are merged.
+    fn to_petgraph(&self) -> DiGraph<usize, ()> {
+        let mut graph: DiGraph<usize, ()> = DiGraph::new();
+        let mut indices = HashMap::new();
+        for &id in self.functions.keys() {
+            indices.insert(id, graph.add_node(id));
+        }
+        for (caller_id, callees) in &self.fn_calls {
+            for (callee_id, _terminator) in callees {
+                if let (Some(&caller), Some(&callee)) =
+                    (indices.get(caller_id), indices.get(callee_id))
+                {
+                    graph.update_edge(caller, callee, ());
+                }
+            }
+        }
+        graph
+    }
+
+    pub fn statistics(&self) -> CallGraphStats {
+        let graph = self.to_petgraph();
+
+        let mut max_in_degree = 0;
+        let mut max_out_degree = 0;
+        for node in graph.node_indices() {
+            let in_degree = graph.neighbors_directed(node, Direction::Incoming).count();
+            let out_degree = graph.neighbors_directed(node, Direction::Outgoing).count();
+            max_in_degree = max_in_degree.max(in_degree);
+            max_out_degree = max_out_degree.max(out_degree);
+        }
+
+        let mut histogram: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut num_recursive_sccs = 0;
+        for scc in tarjan_scc(&graph) {
+            if scc.len() >= 2 {
+                num_recursive_sccs += 1;
+            }
+            *histogram.entry(scc.len()).or_insert(0) += 1;
+        }
+
+        CallGraphStats {
+            num_functions: graph.node_count(),
+            num_edges: graph.edge_count(),
+            max_in_degree,
+            max_out_degree,
+            num_recursive_sccs,
+            num_self_recursive_fns: graph
+                .node_indices()
+                .filter(|node| graph.contains_edge(*node, *node))
+                .count(),
+            strongly_connected_component_size_histogram: histogram.into_iter().collect(),
+        }
+    }
+
+    /// Write the call chains from the root functions to the leaf functions in the collapsed
+    /// stack format of flamegraphs, i.e., one `root;callee;...;leaf 1` line per chain, which can
+    /// be rendered by `flamegraph.pl` or `inferno-flamegraph`.
+    /// Roots are the functions not called by other functions, and one function of each group
+    /// of functions only called by each other. A chain ends before a recursive call, and at
+    /// most `MAX_FLAMEGRAPH_STACKS` chains are written.
+    pub fn export_flamegraph_data(&self, path: &Path) -> std::io::Result<()> {
+        const MAX_FLAMEGRAPH_STACKS: usize = 100_000;
+
+        // a strongly connected component without callers outside of it, e.g., a function
+        // only calling itself or mutually recursive functions only calling each other,
+        // is entered at its first function by def path
+        let graph = self.to_petgraph();
+        let mut roots = Vec::new();
+        for scc in tarjan_scc(&graph) {
+            let members: HashSet<NodeIndex> = scc.iter().copied().collect();
+            let has_outside_caller = scc.iter().any(|node| {
+                graph
+                    .neighbors_directed(*node, Direction::Incoming)
+                    .any(|caller| !members.contains(&caller))
+            });
+            if !has_outside_caller {
+                let root = scc
+                    .iter()
+                    .map(|node| graph[*node])
+                    .min_by_key(|id| self.functions[id].get_def_path())
+                    .unwrap();
+                roots.push(root);
+            }
+        }
+        roots.sort_by_key(|id| self.functions[id].get_def_path());
+
+        let mut stacks = Vec::new();
+        let mut stack = Vec::new();
+        let truncated = roots.into_iter().any(|root| {
+            self.collect_flamegraph_stacks(root, &mut stack, &mut stacks, MAX_FLAMEGRAPH_STACKS)
+        });
+        if truncated {
+            rap_warn!(
+                "Too many call chains, only the first {} are exported",
+                MAX_FLAMEGRAPH_STACKS
+            );
+        }
+
+        let mut file = BufWriter::new(File::create(path)?);
+        for stack in stacks {
+            writeln!(file, "{} 1", stack)?;
+        }
+        file.flush()
+    }
+
+    /// Push the call chains starting with `stack` and then `node_id` to `stacks`. Return true
+    /// if a chain is dropped since `stacks` already has `max_stacks` chains.
+    fn collect_flamegraph_stacks(
+        &self,
+        node_id: usize,
+        stack: &mut Vec<usize>,
+        stacks: &mut Vec<String>,
+        max_stacks: usize,
+    ) -> bool {
+        stack.push(node_id);
+
+        // merge parallel call edges and order callees by def path for a deterministic output
+        let mut callees: Vec<usize> = self
+            .fn_calls
+            .get(&node_id)
+            .map(|callees| {
+                callees
+                    .iter()
+                    .map(|(callee_id, _)| *callee_id)
+                    .filter(|callee_id| !stack.contains(callee_id))
+                    .collect()
+            })
+            .unwrap_or_default();
+        callees.sort_by_key(|id| self.functions[id].get_def_path());
+        callees.dedup();
+
+        let truncated = if callees.is_empty() {
+            if stacks.len() >= max_stacks {
+                true
+            } else {
+                // `;` separates the frames in the collapsed format
+                let frames: Vec<String> = stack
+                    .iter()
+                    .map(|id| self.functions[id].get_def_path().replace(';', ","))
+                    .collect();
+                stacks.push(frames.join(";"));
+                false
+            }
+        } else {
+            callees.into_iter().any(|callee_id| {
+                self.collect_flamegraph_stacks(callee_id, stack, stacks, max_stacks)
+            })
+        };
+        stack.pop();
+        truncated
+    }
+
     pub fn get_reverse_post_order(&self) -> Vec<DefId> {
         let mut visited = HashSet::new();
         let mut post_order_ids = Vec::new(); // Will store the post-order traversal of `usize` IDs
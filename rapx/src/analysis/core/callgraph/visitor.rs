@@ -1,14 +1,27 @@
 use super::default::CallGraphInfo;
+use crate::rap_debug;
 use regex::Regex;
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir;
-use rustc_middle::ty::{FnDef, Instance, InstanceKind, TyCtxt, TypingEnv};
+use rustc_middle::ty::{self, FnDef, GenericArgsRef, Instance, InstanceKind, TyCtxt, TypingEnv};
+use std::collections::HashMap;
+
+/// Upper bound on the number of local-to-local copies followed when resolving an indirect call.
+const MAX_DEVIRTUALIZE_DEPTH: usize = 8;
+
+/// A callee recovered from an indirect call through a local.
+enum IndirectCallee<'tcx> {
+    FnDef(DefId, GenericArgsRef<'tcx>),
+    Closure(DefId),
+}
 
 pub struct CallGraphVisitor<'b, 'tcx> {
     tcx: TyCtxt<'tcx>,
     def_id: DefId,
     body: &'tcx mir::Body<'tcx>,
     call_graph_info: &'b mut CallGraphInfo<'tcx>,
+    /// The operands directly assigned to each local, collected once per body.
+    assignments: HashMap<mir::Local, Vec<&'tcx mir::Operand<'tcx>>>,
 }
 
 impl<'b, 'tcx> CallGraphVisitor<'b, 'tcx> {
@@ -23,7 +36,33 @@ impl<'b, 'tcx> CallGraphVisitor<'b, 'tcx> {
             def_id: def_id,
             body: body,
             call_graph_info: call_graph_info,
+            assignments: Self::collect_assignments(body),
+        }
+    }
+
+    /// Collect the operands assigned to whole locals, either by a direct use or by a
+    /// pointer coercion.
+    fn collect_assignments(
+        body: &'tcx mir::Body<'tcx>,
+    ) -> HashMap<mir::Local, Vec<&'tcx mir::Operand<'tcx>>> {
+        let mut assignments: HashMap<_, Vec<_>> = HashMap::new();
+        for data in body.basic_blocks.iter() {
+            for stmt in data.statements.iter() {
+                let mir::StatementKind::Assign(box (lhs, rvalue)) = &stmt.kind else {
+                    continue;
+                };
+                if !lhs.projection.is_empty() {
+                    continue;
+                }
+                let operand = match rvalue {
+                    mir::Rvalue::Use(operand) => operand,
+                    mir::Rvalue::Cast(mir::CastKind::PointerCoercion(..), operand, _) => operand,
+                    _ => continue,
+                };
+                assignments.entry(lhs.local).or_default().push(operand);
+            }
         }
+        assignments
     }
 
     pub fn add_in_call_graph(
@@ -90,52 +129,125 @@ impl<'b, 'tcx> CallGraphVisitor<'b, 'tcx> {
 
     fn visit_terminator(&mut self, terminator: &'tcx mir::Terminator<'tcx>) {
         if let mir::TerminatorKind::Call { func, .. } = &terminator.kind {
-            if let mir::Operand::Constant(constant) = func {
-                if let FnDef(callee_def_id, callee_substs) = constant.const_.ty().kind() {
-                    let ty_env = TypingEnv::post_analysis(self.tcx, self.def_id);
-                    if let Ok(Some(instance)) =
-                        Instance::try_resolve(self.tcx, ty_env, *callee_def_id, callee_substs)
-                    {
-                        let mut is_virtual = false;
-                        // Try to analysis the specific type of callee.
-                        let instance_def_id = match instance.def {
-                            InstanceKind::Item(def_id) => Some(def_id),
-                            InstanceKind::Intrinsic(def_id) => Some(def_id),
-                            InstanceKind::VTableShim(def_id) => Some(def_id),
-                            InstanceKind::ReifyShim(def_id, _) => Some(def_id),
-                            InstanceKind::FnPtrShim(def_id, _) => Some(def_id),
-                            InstanceKind::Virtual(def_id, _) => {
-                                is_virtual = true;
-                                Some(def_id)
+            match func {
+                mir::Operand::Constant(constant) => {
+                    if let FnDef(callee_def_id, callee_substs) = constant.const_.ty().kind() {
+                        self.resolve_and_add(*callee_def_id, callee_substs, terminator);
+                    }
+                }
+                mir::Operand::Copy(place) | mir::Operand::Move(place)
+                    if place.projection.is_empty() =>
+                {
+                    // Indirect call through a local, e.g. a `fn` pointer or a closure value.
+                    // Try to recover the concrete callee from the local's definition.
+                    for callee in self.devirtualize_local(place.local, 0) {
+                        match callee {
+                            IndirectCallee::FnDef(callee_def_id, callee_substs) => {
+                                self.resolve_and_add(callee_def_id, callee_substs, terminator)
                             }
-                            InstanceKind::ClosureOnceShim { call_once, .. } => Some(call_once),
-                            InstanceKind::ConstructCoroutineInClosureShim {
-                                coroutine_closure_def_id,
-                                ..
-                            } => Some(coroutine_closure_def_id),
-                            InstanceKind::ThreadLocalShim(def_id) => Some(def_id),
-                            InstanceKind::DropGlue(def_id, _) => Some(def_id),
-                            InstanceKind::FnPtrAddrShim(def_id, _) => Some(def_id),
-                            InstanceKind::AsyncDropGlueCtorShim(def_id, _) => Some(def_id),
-                            InstanceKind::CloneShim(def_id, _) => {
-                                if !self.tcx.is_closure_like(def_id) {
-                                    // Not a closure
-                                    Some(def_id)
-                                } else {
-                                    None
-                                }
+                            IndirectCallee::Closure(closure_def_id) => {
+                                self.add_to_call_graph(closure_def_id, None, terminator)
                             }
-                            _ => todo!(),
-                        };
-                        if let Some(instance_def_id) = instance_def_id {
-                            self.add_to_call_graph(instance_def_id, Some(is_virtual), terminator);
                         }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn resolve_and_add(
+        &mut self,
+        callee_def_id: DefId,
+        callee_substs: GenericArgsRef<'tcx>,
+        terminator: &'tcx mir::Terminator<'tcx>,
+    ) {
+        let ty_env = TypingEnv::post_analysis(self.tcx, self.def_id);
+        if let Ok(Some(instance)) =
+            Instance::try_resolve(self.tcx, ty_env, callee_def_id, callee_substs)
+        {
+            let mut is_virtual = false;
+            // Try to analysis the specific type of callee.
+            let instance_def_id = match instance.def {
+                InstanceKind::Item(def_id) => Some(def_id),
+                InstanceKind::Intrinsic(def_id) => Some(def_id),
+                InstanceKind::VTableShim(def_id) => Some(def_id),
+                InstanceKind::ReifyShim(def_id, _) => Some(def_id),
+                InstanceKind::FnPtrShim(def_id, _) => Some(def_id),
+                InstanceKind::Virtual(def_id, _) => {
+                    is_virtual = true;
+                    Some(def_id)
+                }
+                InstanceKind::ClosureOnceShim { call_once, .. } => Some(call_once),
+                InstanceKind::ConstructCoroutineInClosureShim {
+                    coroutine_closure_def_id,
+                    ..
+                } => Some(coroutine_closure_def_id),
+                InstanceKind::ThreadLocalShim(def_id) => Some(def_id),
+                InstanceKind::DropGlue(def_id, _) => Some(def_id),
+                InstanceKind::FnPtrAddrShim(def_id, _) => Some(def_id),
+                InstanceKind::AsyncDropGlueCtorShim(def_id, _) => Some(def_id),
+                InstanceKind::CloneShim(def_id, _) => {
+                    if !self.tcx.is_closure_like(def_id) {
+                        // Not a closure
+                        Some(def_id)
                     } else {
-                        // Although failing to get specific type, callee is still useful.
-                        self.add_to_call_graph(*callee_def_id, None, terminator);
+                        None
                     }
                 }
+                kind => {
+                    rap_debug!("Skip the unsupported instance kind {:?}", kind);
+                    None
+                }
+            };
+            if let Some(instance_def_id) = instance_def_id {
+                self.add_to_call_graph(instance_def_id, Some(is_virtual), terminator);
+            }
+        } else {
+            // Although failing to get specific type, callee is still useful.
+            self.add_to_call_graph(callee_def_id, None, terminator);
+        }
+    }
+
+    /// Find the functions or closures that may flow into `local` within the current body.
+    /// Only direct assignments, copies/moves between locals and pointer coercions are followed.
+    fn devirtualize_local(&self, local: mir::Local, depth: usize) -> Vec<IndirectCallee<'tcx>> {
+        let mut callees = Vec::new();
+        if depth > MAX_DEVIRTUALIZE_DEPTH {
+            return callees;
+        }
+
+        // The local itself may already carry a precise type.
+        match self.body.local_decls[local].ty.kind() {
+            FnDef(def_id, substs) => {
+                callees.push(IndirectCallee::FnDef(*def_id, *substs));
+                return callees;
+            }
+            ty::Closure(def_id, _) => {
+                callees.push(IndirectCallee::Closure(*def_id));
+                return callees;
+            }
+            _ => {}
+        }
+
+        let Some(operands) = self.assignments.get(&local) else {
+            return callees;
+        };
+        for operand in operands {
+            match operand {
+                mir::Operand::Constant(constant) => match constant.const_.ty().kind() {
+                    FnDef(def_id, substs) => callees.push(IndirectCallee::FnDef(*def_id, *substs)),
+                    ty::Closure(def_id, _) => callees.push(IndirectCallee::Closure(*def_id)),
+                    _ => {}
+                },
+                mir::Operand::Copy(place) | mir::Operand::Move(place)
+                    if place.projection.is_empty() =>
+                {
+                    callees.extend(self.devirtualize_local(place.local, depth + 1));
+                }
+                _ => {}
             }
         }
+        callees
     }
 }
@@ -0,0 +1,74 @@
+use crate::rap_debug;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::Body;
+use rustc_middle::ty::TyCtxt;
+
+/// The reason why the MIR of a definition is not returned by `MirAccess::body`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The definition does not own a body, e.g., a foreign function.
+    NoBody,
+    /// The MIR is not available, e.g., a function of another crate without encoded MIR.
+    Unavailable,
+    /// The definition owns a body but is not supported by the MIR queries, e.g., a const param.
+    UnsupportedKind(DefKind),
+}
+
+/// Centralized access to the MIR of body owners.
+/// Calling `optimized_mir` on const contexts or on definitions without MIR makes rustc
+/// panic, so analyses should get MIR through `body`, which selects the right query
+/// according to the kind of the definition and records the skipped definitions.
+pub struct MirAccess<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    skipped: Vec<(DefId, SkipReason)>,
+}
+
+impl<'tcx> MirAccess<'tcx> {
+    pub fn new(tcx: TyCtxt<'tcx>) -> Self {
+        Self {
+            tcx,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Return the MIR of `def_id`: `mir_for_ctfe` for consts and static initializers,
+    /// `optimized_mir` for functions and closures, or `None` if there is no such MIR.
+    pub fn body(&mut self, def_id: DefId) -> Option<&'tcx Body<'tcx>> {
+        match self.select(def_id) {
+            Ok(body) => Some(body),
+            Err(reason) => {
+                rap_debug!("Skip the MIR of {:?}: {:?}", def_id, reason);
+                self.skipped.push((def_id, reason));
+                None
+            }
+        }
+    }
+
+    fn select(&self, def_id: DefId) -> Result<&'tcx Body<'tcx>, SkipReason> {
+        if let Some(local_def_id) = def_id.as_local() {
+            if self.tcx.hir_maybe_body_owned_by(local_def_id).is_none() {
+                return Err(SkipReason::NoBody);
+            }
+        }
+        if !self.tcx.is_mir_available(def_id) {
+            return Err(SkipReason::Unavailable);
+        }
+        match self.tcx.def_kind(def_id) {
+            DefKind::Fn | DefKind::AssocFn | DefKind::Closure | DefKind::SyntheticCoroutineBody => {
+                Ok(self.tcx.optimized_mir(def_id))
+            }
+            DefKind::Const
+            | DefKind::Static { .. }
+            | DefKind::AssocConst
+            | DefKind::InlineConst
+            | DefKind::AnonConst => Ok(self.tcx.mir_for_ctfe(def_id)),
+            def_kind => Err(SkipReason::UnsupportedKind(def_kind)),
+        }
+    }
+
+    /// Return the definitions whose MIR has been skipped, with the reasons.
+    pub fn skipped(&self) -> &[(DefId, SkipReason)] {
+        &self.skipped
+    }
+}
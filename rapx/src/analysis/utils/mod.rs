@@ -1,4 +1,5 @@
 pub mod def_path;
 #[allow(unused)]
 pub mod fn_info;
+pub mod mir;
 pub mod show_mir;
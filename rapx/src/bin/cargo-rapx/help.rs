@@ -14,8 +14,23 @@ Application:
 Analysis:
     -alias          perform alias analysis (meet-over-paths by default)
     -adg            generate API dependency graphs
+    -adg-all        generate API dependency graphs including private functions
+    -adg-synth=<type>
+                    generate API dependency graphs and print API sequences constructing <type>
+    -adg-prefix=<path>
+                    generate API dependency graphs only for functions under the module <path>
+    -adg-root=<path>
+                    generate API dependency graphs only keeping the nodes reachable from the API <path>
+    -adg-stats      generate API dependency graphs and print their statistics, e.g., hub types
+    -adg-skip-trait-impls
+                    generate API dependency graphs without the methods of trait impls
     -audit          (under development) generate unsafe code audit units
     -callgraph      generate callgraphs
+    -callgraph-stats
+                    generate callgraphs and print their statistics, e.g., SCC sizes
+    -callgraph-flamegraph=<path>
+                    generate callgraphs and write their call chains to <path> in the
+                    collapsed flamegraph format
     -dataflow       generate dataflow graphs
     -ownedheap      analyze if the type holds a piece of memory on heap
     -pathcond       extract path constraints
@@ -44,6 +59,8 @@ Environment Variables (Values are case insensitive):
                      trace: print all the detailed RAP execution traces.
                      debug: display intermidiate analysis results.
                      warn: show bugs detected only.
+                     <module>=<level> sets the level of a module, e.g.,
+                     RAP_LOG=warn,analysis::core::callgraph=debug
 
     RAP_CLEAN        run cargo clean before check: true, false
                      * true is the default value except that false is set
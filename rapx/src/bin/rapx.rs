@@ -26,6 +26,10 @@ fn main() {
     let mut args = vec![];
     let mut compiler = RapCallback::default();
     let re_test_crate = Regex::new(r"-test-crate=(\S*)").unwrap();
+    let re_adg_synth = Regex::new(r"-adg-synth=(\S*)").unwrap();
+    let re_adg_prefix = Regex::new(r"-adg-prefix=(\S*)").unwrap();
+    let re_adg_root = Regex::new(r"-adg-root=(\S*)").unwrap();
+    let re_callgraph_flamegraph = Regex::new(r"-callgraph-flamegraph=(\S*)").unwrap();
 
     for arg in env::args() {
         if let Some((_full, [test_crate_name])) =
@@ -34,10 +38,33 @@ fn main() {
             compiler.set_test_crate(test_crate_name.to_owned());
             continue;
         }
+        if let Some((_full, [ty_name])) = re_adg_synth.captures(&arg).map(|caps| caps.extract()) {
+            compiler.enable_api_dependency_synthesis(ty_name);
+            continue;
+        }
+        if let Some((_full, [prefix])) = re_adg_prefix.captures(&arg).map(|caps| caps.extract()) {
+            compiler.add_api_dependency_prefix(prefix);
+            continue;
+        }
+        if let Some((_full, [path])) = re_adg_root.captures(&arg).map(|caps| caps.extract()) {
+            compiler.add_api_dependency_root(path);
+            continue;
+        }
+        if let Some((_full, [path])) = re_callgraph_flamegraph
+            .captures(&arg)
+            .map(|caps| caps.extract())
+        {
+            compiler.enable_callgraph_flamegraph(path);
+            continue;
+        }
         match arg.as_str() {
             "-alias" | "-alias0" | "-alias1" | "-alias2" => compiler.enable_alias(arg),
             "-adg" => compiler.enable_api_dependency(), // api dependency graph
+            "-adg-all" => compiler.enable_api_dependency_all(),
+            "-adg-stats" => compiler.enable_api_dependency_stats(),
+            "-adg-skip-trait-impls" => compiler.enable_api_dependency_skip_trait_impls(),
             "-callgraph" => compiler.enable_callgraph(),
+            "-callgraph-stats" => compiler.enable_callgraph_stats(),
             "-dataflow" => compiler.enable_dataflow(1),
             "-dataflow=debug" => compiler.enable_dataflow(2),
             "-ownedheap" => compiler.enable_ownedheap(),
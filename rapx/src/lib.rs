@@ -32,7 +32,7 @@ use crate::analysis::scan::ScanAnalysis;
 use analysis::{
     core::{
         alias_analysis::{default::AliasAnalyzer, AAResultMapWrapper, AliasAnalysis},
-        api_dependency::ApiDependencyAnalyzer,
+        api_dependency::{ApiDependencyAnalysis, ApiDependencyAnalyzer, Visibility},
         callgraph::{default::CallGraphAnalyzer, CallGraphAnalysis, CallGraphDisplay},
         dataflow::{
             default::DataFlowAnalyzer, Arg2RetMapWrapper, DataFlowAnalysis, DataFlowGraphMapWrapper,
@@ -54,14 +54,16 @@ use analysis::{
 };
 use rustc_ast::ast;
 use rustc_driver::{Callbacks, Compilation};
+use rustc_hir::def_id::LOCAL_CRATE;
 use rustc_interface::{
     interface::{self, Compiler},
     Config,
 };
 use rustc_middle::{ty::TyCtxt, util::Providers};
 use rustc_session::search_paths::PathKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{env, sync::Arc};
+use utils::report;
 
 // Insert rustc arguments at the beginning of the argument list that RAP wants to be
 // set per default, for maximal validation power.
@@ -79,7 +81,15 @@ pub static RAP_DEFAULT_ARGS: &[&str] = &[
 pub struct RapCallback {
     alias: bool,
     api_dependency: bool,
+    api_dependency_synthesis: Option<String>,
+    api_dependency_prefixes: Vec<String>,
+    api_dependency_roots: Vec<String>,
+    api_dependency_visibility: Visibility,
+    api_dependency_stats: bool,
+    api_dependency_skip_trait_impls: bool,
     callgraph: bool,
+    callgraph_stats: bool,
+    callgraph_flamegraph: Option<String>,
     dataflow: usize,
     ownedheap: bool,
     range: usize,
@@ -103,7 +113,15 @@ impl Default for RapCallback {
         Self {
             alias: false,
             api_dependency: false,
+            api_dependency_synthesis: None,
+            api_dependency_prefixes: Vec::new(),
+            api_dependency_roots: Vec::new(),
+            api_dependency_visibility: Visibility::Public,
+            api_dependency_stats: false,
+            api_dependency_skip_trait_impls: false,
             callgraph: false,
+            callgraph_stats: false,
+            callgraph_flamegraph: None,
             dataflow: 0,
             ownedheap: false,
             range: 0,
@@ -215,11 +233,55 @@ impl RapCallback {
         self.api_dependency = true;
     }
 
+    /// Enable API-dependency graph generation and print the API sequences that construct
+    /// the given type.
+    pub fn enable_api_dependency_synthesis(&mut self, ty_name: impl ToString) {
+        self.api_dependency = true;
+        self.api_dependency_synthesis = Some(ty_name.to_string());
+    }
+
+    /// Enable API-dependency graph generation, only including functions whose def path
+    /// starts with `prefix`. Can be called multiple times to include several modules.
+    pub fn add_api_dependency_prefix(&mut self, prefix: impl ToString) {
+        self.api_dependency = true;
+        self.api_dependency_prefixes.push(prefix.to_string());
+    }
+
+    /// Enable API-dependency graph generation, only keeping the nodes reachable from the
+    /// APIs with path `path`. Can be called multiple times to add several roots.
+    pub fn add_api_dependency_root(&mut self, path: impl ToString) {
+        self.api_dependency = true;
+        self.api_dependency_roots.push(path.to_string());
+    }
+
     /// Test if API-dependency graph generation is enabled.
     pub fn is_api_dependency_enabled(&self) -> bool {
         self.api_dependency
     }
 
+    /// Enable API-dependency graph generation, including private functions.
+    pub fn enable_api_dependency_all(&mut self) {
+        self.api_dependency = true;
+        self.api_dependency_visibility = Visibility::All;
+    }
+
+    /// Enable API-dependency graph generation and print its statistics.
+    pub fn enable_api_dependency_stats(&mut self) {
+        self.api_dependency = true;
+        self.api_dependency_stats = true;
+    }
+
+    /// Test if API-dependency graph statistics should be printed.
+    pub fn is_api_dependency_stats_enabled(&self) -> bool {
+        self.api_dependency_stats
+    }
+
+    /// Enable API-dependency graph generation, skipping the methods of trait impls.
+    pub fn enable_api_dependency_skip_trait_impls(&mut self) {
+        self.api_dependency = true;
+        self.api_dependency_skip_trait_impls = true;
+    }
+
     /// Enable call-graph analysis.
     pub fn enable_callgraph(&mut self) {
         self.callgraph = true;
@@ -230,6 +292,24 @@ impl RapCallback {
         self.callgraph
     }
 
+    /// Enable call-graph analysis and print its structural statistics.
+    pub fn enable_callgraph_stats(&mut self) {
+        self.callgraph = true;
+        self.callgraph_stats = true;
+    }
+
+    /// Test if call-graph statistics should be printed.
+    pub fn is_callgraph_stats_enabled(&self) -> bool {
+        self.callgraph_stats
+    }
+
+    /// Enable call-graph analysis and write its call chains to `path` in the collapsed
+    /// flamegraph format.
+    pub fn enable_callgraph_flamegraph(&mut self, path: impl ToString) {
+        self.callgraph = true;
+        self.callgraph_flamegraph = Some(path.to_string());
+    }
+
     /// Enable owned heap analysis.
     pub fn enable_ownedheap(&mut self) {
         self.ownedheap = true;
@@ -392,6 +472,10 @@ impl RapCallback {
 
 /// Start the analysis with the features enabled.
 pub fn start_analyzer(tcx: TyCtxt, callback: &RapCallback) {
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let crate_type = tcx.crate_types()[0].to_string();
+    report::set_target(".", crate_name.as_str(), &crate_type);
+
     if callback.is_alias_enabled() {
         let mut analyzer = AliasAnalyzer::new(tcx);
         analyzer.run();
@@ -403,17 +487,28 @@ pub fn start_analyzer(tcx: TyCtxt, callback: &RapCallback) {
         let mut analyzer = ApiDependencyAnalyzer::new(
             tcx,
             analysis::core::api_dependency::Config {
-                pub_only: true,
-                resolve_generic: true,
-                ignore_const_generic: true,
+                skip_trait_impls: callback.api_dependency_skip_trait_impls,
+                path_prefixes: callback.api_dependency_prefixes.clone(),
+                ..analysis::core::api_dependency::Config::for_visibility(
+                    callback.api_dependency_visibility,
+                )
             },
         );
-        analyzer.run();
+        if let Some(ty_name) = &callback.api_dependency_synthesis {
+            analyzer.set_synthesis_target(ty_name);
+        }
+        for root in &callback.api_dependency_roots {
+            analyzer.add_root(root);
+        }
+        report::run_analysis("api_dependency", || analyzer.run());
+        if callback.is_api_dependency_stats_enabled() {
+            rap_info!("{}", analyzer.get_api_dependency_graph().statistics());
+        }
     }
 
     if callback.is_callgraph_enabled() {
         let mut analyzer = CallGraphAnalyzer::new(tcx);
-        analyzer.run();
+        report::run_analysis("callgraph", || analyzer.run());
         let callgraph = analyzer.get_callgraph();
         rap_info!(
             "{}",
@@ -423,6 +518,15 @@ pub fn start_analyzer(tcx: TyCtxt, callback: &RapCallback) {
             }
         );
         //analyzer.display();
+        if callback.is_callgraph_stats_enabled() {
+            rap_info!("{}", analyzer.graph.statistics());
+        }
+        if let Some(path) = &callback.callgraph_flamegraph {
+            match analyzer.graph.export_flamegraph_data(Path::new(path)) {
+                Ok(()) => rap_info!("Dump call chains of the callgraph to {}", path),
+                Err(e) => rap_warn!("Failed to dump call chains to {}: {}", path, e),
+            }
+        }
     }
 
     match callback.is_dataflow_enabled() {
@@ -534,4 +638,10 @@ pub fn start_analyzer(tcx: TyCtxt, callback: &RapCallback) {
     if callback.is_scan_enabled() {
         ScanAnalysis::new(tcx).run();
     }
+
+    match report::write_report(".", crate_name.as_str(), &crate_type) {
+        Ok(Some(path)) => rap_info!("Write analysis report to {}", path.display()),
+        Ok(None) => {}
+        Err(e) => rap_warn!("Failed to write analysis report: {}", e),
+    }
 }
@@ -6,20 +6,53 @@ use rustc_span::source_map::get_source_map;
 use rustc_span::{FileNameDisplayPreference, Pos, Span};
 use std::ops::Range;
 
-fn log_level() -> LevelFilter {
-    if let Ok(s) = std::env::var("RAP_LOG") {
-        match s.parse() {
-            Ok(level) => return level,
-            Err(err) => eprintln!("RAP_LOG is invalid: {err}"),
+/// The crates logging through this module: the library and the binaries.
+const LOG_CRATES: &[&str] = &["rapx", "cargo_rapx"];
+
+fn is_crate_qualified(target: &str) -> bool {
+    let krate = target.split("::").next().unwrap_or_default();
+    LOG_CRATES.contains(&krate)
+}
+
+/// Parse `RAP_LOG` into the default level and the levels of specific modules, e.g.,
+/// `info,analysis::core::callgraph=debug`. Each item is either a level, which sets the
+/// default level, or `<module path>=<level>`. Module paths are relative to the root of the
+/// `rapx` crate, unless they start with one of `LOG_CRATES`, e.g., `cargo_rapx::cargo_check`.
+fn log_filter() -> (LevelFilter, Vec<(String, LevelFilter)>) {
+    let mut default = LevelFilter::Info;
+    let mut targets = Vec::new();
+    let Ok(s) = std::env::var("RAP_LOG") else {
+        return (default, targets);
+    };
+    for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let (target, level) = match item.split_once('=') {
+            Some((target, level)) => (Some(target.trim()), level.trim()),
+            None => (None, item),
+        };
+        let level = match level.parse() {
+            Ok(level) => level,
+            Err(err) => {
+                eprintln!("RAP_LOG is invalid: {item}: {err}");
+                continue;
+            }
+        };
+        match target {
+            Some(target) if is_crate_qualified(target) => targets.push((target.to_owned(), level)),
+            Some(target) => targets.push((format!("rapx::{target}"), level)),
+            None => default = level,
         }
     }
-    LevelFilter::Info
+    (default, targets)
 }
 
 /// Detect `RAP_LOG` environment variable first; if it's not set,
 /// default to INFO level.
 pub fn init_log() -> Result<(), fern::InitError> {
-    let dispatch = Dispatch::new().level(log_level());
+    let (level, targets) = log_filter();
+    let mut dispatch = Dispatch::new().level(level);
+    for (target, level) in targets {
+        dispatch = dispatch.level_for(target, level);
+    }
 
     let color_line = ColoredLevelConfig::new()
         .error(Color::Red)
@@ -54,38 +87,39 @@ pub fn init_log() -> Result<(), fern::InitError> {
     Ok(())
 }
 
+// The target of a log is the module path, so that `RAP_LOG` can set levels per module.
 #[macro_export]
 macro_rules! rap_trace {
     ($($arg:tt)+) => (
-        ::log::trace!(target: "RAP", $($arg)+)
+        ::log::trace!(target: module_path!(), $($arg)+)
     );
 }
 
 #[macro_export]
 macro_rules! rap_debug {
     ($($arg:tt)+) => (
-        ::log::debug!(target: "RAP", $($arg)+)
+        ::log::debug!(target: module_path!(), $($arg)+)
     );
 }
 
 #[macro_export]
 macro_rules! rap_info {
     ($($arg:tt)+) => (
-        ::log::info!(target: "RAP", $($arg)+)
+        ::log::info!(target: module_path!(), $($arg)+)
     );
 }
 
 #[macro_export]
 macro_rules! rap_warn {
     ($($arg:tt)+) => (
-        ::log::warn!(target: "RAP", $($arg)+)
+        ::log::warn!(target: module_path!(), $($arg)+)
     );
 }
 
 #[macro_export]
 macro_rules! rap_error {
     ($($arg:tt)+) => (
-        ::log::error!(target: "RAP", $($arg)+)
+        ::log::error!(target: module_path!(), $($arg)+)
     );
 }
 
@@ -1,4 +1,5 @@
 pub mod fs;
 #[macro_use]
 pub mod log;
+pub mod report;
 pub mod source;
@@ -0,0 +1,156 @@
+//! A single machine-readable manifest of the results of all analyses in one run.
+//!
+//! Each analysis registers its artifacts (JSON values or paths of dumped files) under a
+//! section named after the analysis. `write_report` writes all sections, together with the
+//! rapx version and the name and type of the target crate, to
+//! `rapx-report-{crate_name}-{crate_type}.json`, so the crates of one cargo invocation do not
+//! overwrite each other's reports.
+
+use crate::rap_warn;
+use serde::Serialize;
+use serde_json::Value;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "value")]
+pub enum Artifact {
+    Json(Value),
+    File(PathBuf),
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Running,
+    Done,
+    /// the analysis panicked, and the run was aborted
+    Failed,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct Section {
+    status: Status,
+    duration_ms: Option<u128>,
+    /// the panic message of a failed analysis
+    error: Option<String>,
+    artifacts: BTreeMap<String, Artifact>,
+}
+
+#[derive(Serialize, Debug)]
+struct Report<'a> {
+    version: &'static str,
+    crate_name: &'a str,
+    crate_type: &'a str,
+    sections: &'a BTreeMap<String, Section>,
+}
+
+static SECTIONS: LazyLock<Mutex<BTreeMap<String, Section>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// the directory, crate name and crate type of the report, see `set_target`
+static TARGET: Mutex<Option<(PathBuf, String, String)>> = Mutex::new(None);
+
+/// Register an artifact named `name` in the section of `analysis`.
+pub fn register(analysis: &str, name: &str, artifact: Artifact) {
+    let mut sections = SECTIONS.lock().unwrap();
+    sections
+        .entry(analysis.to_owned())
+        .or_default()
+        .artifacts
+        .insert(name.to_owned(), artifact);
+}
+
+/// Set the directory and the crate that the report is written for if an analysis panics.
+pub fn set_target(dir: impl Into<PathBuf>, crate_name: &str, crate_type: &str) {
+    *TARGET.lock().unwrap() = Some((dir.into(), crate_name.to_owned(), crate_type.to_owned()));
+}
+
+/// Run `f` as the analysis `analysis`, and record its status and duration in the report.
+/// If `f` panics, the analysis is recorded as failed with the panic message, the report is
+/// written for the target set by `set_target`, and the panic is resumed. The panic is not
+/// recovered from, since the compiler state, e.g., a query that was being computed, is
+/// broken after it.
+pub fn run_analysis<R>(analysis: &str, f: impl FnOnce() -> R) -> R {
+    SECTIONS
+        .lock()
+        .unwrap()
+        .entry(analysis.to_owned())
+        .or_default();
+    let start = Instant::now();
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => {
+            finish(analysis, start.elapsed(), None);
+            result
+        }
+        Err(payload) => {
+            finish(
+                analysis,
+                start.elapsed(),
+                Some(panic_message(payload.as_ref())),
+            );
+            let target = TARGET.lock().unwrap().clone();
+            if let Some((dir, crate_name, crate_type)) = target {
+                if let Err(e) = write_report(&dir, &crate_name, &crate_type) {
+                    rap_warn!("Failed to write analysis report: {}", e);
+                }
+            }
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+fn finish(analysis: &str, duration: Duration, error: Option<String>) {
+    let mut sections = SECTIONS.lock().unwrap();
+    let section = sections.entry(analysis.to_owned()).or_default();
+    section.status = if error.is_some() {
+        Status::Failed
+    } else {
+        Status::Done
+    };
+    section.duration_ms = Some(duration.as_millis());
+    section.error = error;
+}
+
+/// Return the file name of the report of the given crate.
+pub fn report_file_name(crate_name: &str, crate_type: &str) -> String {
+    format!("rapx-report-{}-{}.json", crate_name, crate_type)
+}
+
+/// Write the report to `dir`, see `report_file_name`. Nothing is written if no analysis has
+/// registered in the report. Return the path of the written report.
+pub fn write_report(
+    dir: impl AsRef<Path>,
+    crate_name: &str,
+    crate_type: &str,
+) -> std::io::Result<Option<PathBuf>> {
+    let sections = SECTIONS.lock().unwrap();
+    if sections.is_empty() {
+        return Ok(None);
+    }
+    let report = Report {
+        version: env!("CARGO_PKG_VERSION"),
+        crate_name,
+        crate_type,
+        sections: &*sections,
+    };
+    let path = dir.as_ref().join(report_file_name(crate_name, crate_type));
+    let file = std::fs::File::create(&path)?;
+    serde_json::to_writer_pretty(file, &report)?;
+    Ok(Some(path))
+}
@@ -0,0 +1,29 @@
+pub struct Foo {
+    value: u32,
+}
+
+pub struct Bar {
+    foo: Foo,
+}
+
+impl Bar {
+    pub fn with_foo(self, foo: Foo) -> Bar {
+        Bar { foo }
+    }
+}
+
+pub fn new_foo(value: u32) -> Foo {
+    Foo { value }
+}
+
+pub fn foo_value(foo: &Foo) -> u32 {
+    foo.value
+}
+
+pub fn new_bar(foo: Foo) -> Bar {
+    Bar { foo }
+}
+
+pub fn bar_value(bar: &Bar) -> u32 {
+    foo_value(&bar.foo)
+}
@@ -0,0 +1,41 @@
+pub struct Foo(pub u32);
+
+pub struct Holder {
+    first: Foo,
+    second: Foo,
+}
+
+pub type FooRef<'a> = &'a Foo;
+
+pub fn new_holder(first: u32, second: u32) -> Holder {
+    Holder {
+        first: Foo(first),
+        second: Foo(second),
+    }
+}
+
+pub fn first<'x>(holder: &'x Holder) -> &'x Foo {
+    &holder.first
+}
+
+pub fn second<'y>(holder: &'y Holder) -> FooRef<'y> {
+    &holder.second
+}
+
+pub trait Tr {
+    type Out;
+}
+
+pub struct Bar(pub u32);
+
+impl Tr for Foo {
+    type Out = Bar;
+}
+
+pub fn foo_out(foo: &Foo) -> <Foo as Tr>::Out {
+    Bar(foo.0)
+}
+
+pub fn new_bar(value: u32) -> Bar {
+    Bar(value)
+}
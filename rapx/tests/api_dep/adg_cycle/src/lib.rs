@@ -0,0 +1,41 @@
+pub struct Node {
+    id: u32,
+}
+
+pub struct Edge {
+    from: u32,
+}
+
+pub struct Config {
+    verbose: bool,
+}
+
+pub fn new_config(verbose: bool) -> Config {
+    Config { verbose }
+}
+
+pub fn node_of(edge: &Edge) -> Node {
+    Node { id: edge.from }
+}
+
+pub fn edge_of(node: &Node) -> Edge {
+    Edge { from: node.id }
+}
+
+pub struct Builder {
+    size: u32,
+}
+
+pub fn new_builder() -> Builder {
+    Builder { size: 0 }
+}
+
+pub fn copy_builder(builder: &Builder) -> Builder {
+    Builder { size: builder.size }
+}
+
+pub fn with_size(builder: Builder, size: u32) -> Builder {
+    Builder {
+        size: builder.size + size,
+    }
+}
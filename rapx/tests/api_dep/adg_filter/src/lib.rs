@@ -0,0 +1,43 @@
+pub mod shapes {
+    pub struct Circle {
+        radius: u32,
+    }
+
+    pub fn new_circle(radius: u32) -> Circle {
+        Circle { radius }
+    }
+
+    pub fn area(circle: &Circle) -> u32 {
+        3 * square(circle)
+    }
+
+    fn square(circle: &Circle) -> u32 {
+        circle.radius * circle.radius
+    }
+}
+
+pub mod shapes_ext {
+    use super::shapes::{new_circle, Circle};
+
+    pub fn unit_circle() -> Circle {
+        new_circle(1)
+    }
+}
+
+pub mod colors {
+    pub struct Rgb(pub u8, pub u8, pub u8);
+
+    pub fn black() -> Rgb {
+        Rgb(0, 0, 0)
+    }
+
+    impl Default for Rgb {
+        fn default() -> Self {
+            black()
+        }
+    }
+}
+
+fn unused() -> u32 {
+    shapes::area(&shapes::new_circle(1)) + colors::black().0 as u32
+}
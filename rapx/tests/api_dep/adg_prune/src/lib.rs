@@ -0,0 +1,31 @@
+pub struct Foo {
+    value: u32,
+}
+
+pub struct Bar {
+    foo: Foo,
+}
+
+pub struct Baz {
+    id: u64,
+}
+
+pub fn new_foo(value: u32) -> Foo {
+    Foo { value }
+}
+
+pub fn new_bar(foo: Foo) -> Bar {
+    Bar { foo }
+}
+
+pub fn bar_name(bar: &Bar) -> String {
+    format!("bar {}", bar.foo.value)
+}
+
+pub fn new_baz(id: u64) -> Baz {
+    Baz { id }
+}
+
+pub fn baz_id(baz: &Baz) -> u64 {
+    baz.id
+}
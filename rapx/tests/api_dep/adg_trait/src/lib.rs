@@ -0,0 +1,35 @@
+pub trait Shape {
+    fn area(&self) -> u32;
+
+    fn describe(&self) -> String {
+        format!("shape with area {}", self.area())
+    }
+}
+
+pub struct Square(pub u32);
+
+pub struct Rect(pub u32, pub u32);
+
+struct Hidden;
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+}
+
+impl Shape for Rect {
+    fn area(&self) -> u32 {
+        self.0 * self.1
+    }
+
+    fn describe(&self) -> String {
+        format!("rect {}x{}", self.0, self.1)
+    }
+}
+
+impl Shape for Hidden {
+    fn area(&self) -> u32 {
+        0
+    }
+}
@@ -0,0 +1,13 @@
+pub struct Foo {
+    value: u32,
+}
+
+pub fn new_foo(value: u32) -> Foo {
+    Foo {
+        value: checked(value),
+    }
+}
+
+fn checked(value: u32) -> u32 {
+    value.min(100)
+}
@@ -0,0 +1,16 @@
+const fn square(x: u32) -> u32 {
+    x * x
+}
+
+fn cube(x: u32) -> u32 {
+    x * square(x)
+}
+
+const SIDE: u32 = square(3);
+
+static AREA: u32 = square(4);
+
+fn main() {
+    let volume = |x: u32| cube(x);
+    println!("{} {} {}", SIDE, AREA, volume(2));
+}
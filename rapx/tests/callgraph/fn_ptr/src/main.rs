@@ -0,0 +1,18 @@
+fn double(x: i32) -> i32 {
+    x * 2
+}
+
+fn triple(x: i32) -> i32 {
+    x * 3
+}
+
+fn main() {
+    let f: fn(i32) -> i32 = if std::env::args().count() > 1 {
+        triple
+    } else {
+        double
+    };
+    let add_one = |x: i32| x + 1;
+    let g: fn(i32) -> i32 = add_one;
+    println!("{}", g(f(1)));
+}
@@ -0,0 +1,11 @@
+pub struct Answer {
+    value: u32,
+}
+
+pub fn answer() -> Answer {
+    Answer { value: 42 }
+}
+
+pub fn answer_value(answer: &Answer) -> u32 {
+    answer.value
+}
@@ -1,21 +1,27 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
 
-#[inline(always)]
-fn running_tests_with_arg(dir: &str, arg: &str) -> String {
+/// Run `cargo rapx` with `args` in the fixture `dir` under `tests/`, with the extra
+/// environment variables `envs`, and return its stderr.
+fn running_tests(dir: &str, args: &[&str], envs: &[(&str, &str)]) -> String {
     let raw_path = "./tests/".to_owned() + dir;
-    let project_path = Path::new(&raw_path);
-
     let output = Command::new("cargo")
         .arg("rapx")
-        .arg(arg)
-        .current_dir(project_path)
+        .args(args)
+        .envs(envs.iter().copied())
+        .current_dir(Path::new(&raw_path))
         .output()
         .expect("Failed to execute cargo rapx");
 
     String::from_utf8_lossy(&output.stderr).into_owned()
 }
 
+#[inline(always)]
+fn running_tests_with_arg(dir: &str, arg: &str) -> String {
+    running_tests(dir, &[arg], &[])
+}
+
 #[test]
 fn test_dangling_min() {
     let output = running_tests_with_arg("uaf/dangling_min", "-F");
@@ -301,3 +307,475 @@ fn test_interprocedual_range_analysis() {
         );
     }
 }
+
+/// Tests sharing a fixture run `cargo rapx` in it only once, since concurrent runs in one
+/// directory race on `cargo clean` and on the dumped files.
+fn callgraph_fn_ptr_output() -> &'static str {
+    static OUTPUT: OnceLock<String> = OnceLock::new();
+    OUTPUT.get_or_init(|| {
+        running_tests(
+            "callgraph/fn_ptr",
+            &["-callgraph", "-callgraph-flamegraph=callgraph.folded"],
+            &[],
+        )
+    })
+}
+
+#[test]
+fn test_callgraph_fn_ptr() {
+    let output = callgraph_fn_ptr_output();
+    assert!(output.contains("-> double"));
+    assert!(output.contains("-> triple"));
+    assert!(output.contains("-> main::{closure#0}"));
+}
+
+#[inline(always)]
+fn find_api_graph_json(dir: &str) -> PathBuf {
+    let raw_path = "./tests/".to_owned() + dir;
+    std::fs::read_dir(raw_path)
+        .expect("Failed to read test directory")
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with("api_graph_") && name.ends_with(".json")
+        })
+        .expect("API graph json is not generated")
+}
+
+fn read_api_graph_json(dir: &str) -> serde_json::Value {
+    let content = std::fs::read_to_string(find_api_graph_json(dir)).unwrap();
+    serde_json::from_str(&content).unwrap()
+}
+
+fn api_graph_json(dir: &str, arg: &str) -> serde_json::Value {
+    running_tests_with_arg(dir, arg);
+    read_api_graph_json(dir)
+}
+
+fn adg_basic_output() -> &'static str {
+    static OUTPUT: OnceLock<String> = OnceLock::new();
+    OUTPUT
+        .get_or_init(|| running_tests("api_dep/adg_basic", &["-adg-synth=Bar", "-adg-stats"], &[]))
+}
+
+#[test]
+fn test_api_dep_json_roundtrip() {
+    let output = adg_basic_output();
+    let graph = read_api_graph_json("api_dep/adg_basic");
+    let nodes = graph["nodes"].as_array().unwrap();
+    let edges = graph["edges"].as_array().unwrap();
+
+    let num_api = nodes.iter().filter(|node| node["kind"] == "api").count();
+    let num_ty = nodes.iter().filter(|node| node["kind"] == "type").count();
+    let expected = format!(
+        "API Graph contains {} API nodes, {} type nodes, {} edges",
+        num_api,
+        num_ty,
+        edges.len()
+    );
+    assert!(
+        output.contains(&expected),
+        "Missing '{}'\nFull output:\n{}",
+        expected,
+        output
+    );
+
+    // ids are the positions in the arrays, and every edge refers to existing nodes
+    for (no, node) in nodes.iter().enumerate() {
+        assert_eq!(node["id"].as_u64(), Some(no as u64));
+    }
+    for edge in edges {
+        assert!((edge["from"].as_u64().unwrap() as usize) < nodes.len());
+        assert!((edge["to"].as_u64().unwrap() as usize) < nodes.len());
+    }
+    assert!(nodes
+        .iter()
+        .any(|node| node["kind"] == "api" && node["path"] == "new_foo"));
+}
+
+#[test]
+fn test_api_dep_synthesis_paths() {
+    let output = adg_basic_output();
+    assert!(
+        output.contains("new_foo -> new_bar"),
+        "Missing synthesis path for Bar\nFull output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("new_bar as fn(Foo) -> Bar // src/lib.rs:23"),
+        "Missing signature of new_bar\nFull output:\n{}",
+        output
+    );
+    // the builder method constructs `Bar` from a `Bar` built by `new_bar`
+    assert!(
+        output.contains("new_bar -> new_foo -> Bar::with_foo"),
+        "Missing synthesis path through the builder\nFull output:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_api_dep_filter() {
+    let output = running_tests_with_arg("api_dep/adg_filter", "-adg");
+    assert!(
+        output.contains("API Graph contains 5 API nodes"),
+        "Unexpected API count\nFull output:\n{}",
+        output
+    );
+
+    // `shapes_ext` is not under the module `shapes`
+    let output = running_tests_with_arg("api_dep/adg_filter", "-adg-prefix=shapes");
+    assert!(
+        output.contains("API Graph contains 2 API nodes"),
+        "Unexpected API count with prefix filter\nFull output:\n{}",
+        output
+    );
+
+    // trait impl methods are matched by their module, not by their def path
+    let graph = api_graph_json("api_dep/adg_filter", "-adg-prefix=colors");
+    let apis: Vec<&str> = graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|node| node["kind"] == "api")
+        .map(|node| node["path"].as_str().unwrap())
+        .collect();
+    assert!(
+        apis.contains(&"colors::black"),
+        "Unexpected APIs: {:?}",
+        apis
+    );
+    assert!(
+        apis.iter()
+            .any(|api| api.starts_with("<colors::Rgb as") && api.ends_with("Default>::default")),
+        "Missing the Default impl of Rgb: {:?}",
+        apis
+    );
+
+    let output = running_tests_with_arg("api_dep/adg_filter", "-adg-skip-trait-impls");
+    assert!(
+        output.contains("API Graph contains 4 API nodes"),
+        "Unexpected API count without trait impls\nFull output:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_api_dep_trait_methods() {
+    let graph = api_graph_json("api_dep/adg_trait", "-adg");
+    let nodes = graph["nodes"].as_array().unwrap();
+    let edges = graph["edges"].as_array().unwrap();
+
+    let api_index = |path: &str, self_ty: &str| {
+        nodes
+            .iter()
+            .position(|node| {
+                node["kind"] == "api"
+                    && node["path"] == path
+                    && (node["args"].as_array().unwrap().is_empty() || node["args"][0] == self_ty)
+            })
+            .unwrap_or_else(|| panic!("Missing API {} for {}", path, self_ty))
+    };
+    // the type node connected to the API by `kind` edge
+    let ty_of = |api: usize, kind: &str| -> String {
+        edges
+            .iter()
+            .find_map(|edge| {
+                let (from, to) = (
+                    edge["from"].as_u64()? as usize,
+                    edge["to"].as_u64()? as usize,
+                );
+                if edge["kind"] != kind {
+                    None
+                } else if kind == "ret" && from == api {
+                    Some(nodes[to]["path"].as_str()?.to_owned())
+                } else if kind != "ret" && to == api {
+                    Some(nodes[from]["path"].as_str()?.to_owned())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| panic!("Missing {} edge of {}", kind, nodes[api]["path"]))
+    };
+
+    let square_area = api_index("<Square as Shape>::area", "Square");
+    let rect_area = api_index("<Rect as Shape>::area", "Rect");
+    assert_eq!(ty_of(square_area, "arg(0)"), "&Square");
+    assert_eq!(ty_of(rect_area, "arg(0)"), "&Rect");
+    assert_eq!(ty_of(square_area, "ret"), "u32");
+
+    // the provided method is instantiated for the impl that does not override it
+    let square_describe = api_index("Shape::describe", "Square");
+    assert_eq!(ty_of(square_describe, "arg(0)"), "&Square");
+    assert!(ty_of(square_describe, "ret").ends_with("String"));
+
+    // `Rect` overrides the provided method, so it is not instantiated for `Rect`
+    assert!(!nodes.iter().any(|node| {
+        node["kind"] == "api" && node["path"] == "Shape::describe" && node["args"][0] == "Rect"
+    }));
+
+    // the provided method is not instantiated for the private `Hidden` either
+    assert!(!nodes.iter().any(|node| {
+        node["kind"] == "api" && node["path"] == "Shape::describe" && node["args"][0] == "Hidden"
+    }));
+}
+
+fn adg_canonical_json() -> &'static serde_json::Value {
+    static GRAPH: OnceLock<serde_json::Value> = OnceLock::new();
+    GRAPH.get_or_init(|| api_graph_json("api_dep/adg_canonical", "-adg"))
+}
+
+#[test]
+fn test_api_dep_canonical_ty() {
+    let graph = adg_canonical_json();
+    let nodes = graph["nodes"].as_array().unwrap();
+    let num_foo_ref = nodes
+        .iter()
+        .filter(|node| node["kind"] == "type" && node["path"] == "&Foo")
+        .count();
+    assert_eq!(num_foo_ref, 1);
+
+    // `<Foo as Tr>::Out` is normalized to `Bar`, keeping the written form as the label
+    let bar_nodes: Vec<_> = nodes
+        .iter()
+        .filter(|node| node["kind"] == "type" && node["path"] == "Bar")
+        .collect();
+    assert_eq!(bar_nodes.len(), 1);
+    assert_eq!(bar_nodes[0]["label"], "<Foo as Tr>::Out");
+    assert!(!nodes
+        .iter()
+        .any(|node| node["kind"] == "type" && node["path"] == "<Foo as Tr>::Out"));
+}
+
+#[test]
+fn test_api_dep_return_borrows_from() {
+    let graph = adg_canonical_json();
+    let borrows_from = |name: &str| {
+        graph["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|node| node["kind"] == "api" && node["path"] == name)
+            .map(|node| node["borrows_from"].clone())
+            .unwrap()
+    };
+    assert_eq!(borrows_from("first"), serde_json::json!([0]));
+    assert_eq!(borrows_from("second"), serde_json::json!([0]));
+    assert_eq!(borrows_from("new_holder"), serde_json::json!([]));
+}
+
+#[test]
+fn test_api_dep_prune() {
+    let output = running_tests_with_arg("api_dep/adg_prune", "-adg-root=new_bar");
+    assert!(
+        output.contains("Prune API graph from 1 roots"),
+        "API graph is not pruned\nFull output:\n{}",
+        output
+    );
+
+    let graph = read_api_graph_json("api_dep/adg_prune");
+    let apis: Vec<&str> = graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|node| node["kind"] == "api")
+        .map(|node| node["path"].as_str().unwrap())
+        .collect();
+    // `new_foo` produces the input of `new_bar`, so it is kept to call `new_bar`
+    for kept in ["new_bar", "new_foo"] {
+        assert!(apis.contains(&kept), "{} is pruned: {:?}", kept, apis);
+    }
+    for unreachable in ["new_baz", "baz_id"] {
+        assert!(
+            !apis.contains(&unreachable),
+            "{} is not pruned: {:?}",
+            unreachable,
+            apis
+        );
+    }
+
+    // nothing is pruned if no API is named by the root
+    let output = running_tests_with_arg("api_dep/adg_prune", "-adg-root=no_such_api");
+    assert!(
+        output.contains("skip pruning") && !output.contains("Prune API graph"),
+        "Unexpected pruning without roots\nFull output:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_api_dep_visibility() {
+    let api_paths = |graph: serde_json::Value| -> Vec<String> {
+        graph["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|node| node["kind"] == "api")
+            .map(|node| node["path"].as_str().unwrap().to_owned())
+            .collect()
+    };
+
+    let public = api_paths(api_graph_json("api_dep/adg_visibility", "-adg"));
+    assert!(public.contains(&"new_foo".to_owned()));
+    assert!(!public.contains(&"checked".to_owned()));
+
+    let all = api_paths(api_graph_json("api_dep/adg_visibility", "-adg-all"));
+    assert!(all.contains(&"new_foo".to_owned()));
+    assert!(all.contains(&"checked".to_owned()));
+}
+
+#[test]
+fn test_report_manifest() {
+    adg_basic_output();
+    let content =
+        std::fs::read_to_string("./tests/api_dep/adg_basic/rapx-report-adg_basic-rlib.json")
+            .unwrap();
+    let report: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(report["crate_name"], "adg_basic");
+    assert_eq!(report["crate_type"], "rlib");
+    assert!(report["version"].is_string());
+
+    let section = &report["sections"]["api_dependency"];
+    assert_eq!(section["status"], "done");
+    assert!(section["duration_ms"].is_u64());
+    assert!(section["artifacts"]["statistics"]["value"]["api_count"].is_u64());
+    let json_path = section["artifacts"]["json"]["value"].as_str().unwrap();
+    assert!(Path::new("./tests/api_dep/adg_basic")
+        .join(json_path)
+        .exists());
+}
+
+#[test]
+fn test_api_dep_type_cycle() {
+    let output = running_tests_with_arg("api_dep/adg_cycle", "-adg");
+    assert!(
+        output.contains("1 cyclic components"),
+        "Unexpected cyclic components, only Node and Edge are cyclic\nFull output:\n{}",
+        output
+    );
+
+    let output = adg_basic_output();
+    assert!(
+        output.contains("0 cyclic components"),
+        "Unexpected cyclic component\nFull output:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_log_module_filter() {
+    let output = running_tests(
+        "log/log_filter",
+        &["-adg"],
+        &[(
+            "RAP_LOG",
+            "info,analysis::core::api_dependency=warn,cargo_rapx::cargo_check=trace",
+        )],
+    );
+    assert!(
+        !output.contains("API Graph contains"),
+        "Info log of api_dependency is not filtered\nFull output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Write analysis report"),
+        "Missing info log of other modules\nFull output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("cargo check in package folder"),
+        "Missing trace log of cargo-rapx\nFull output:\n{}",
+        output
+    );
+
+    // the default verbosity only prints the summaries of the analysis
+    const MAX_DEFAULT_LINES: usize = 40;
+    let output = running_tests_with_arg("log/log_filter", "-adg");
+    assert!(
+        output.contains("API Graph contains"),
+        "Missing info log of api_dependency\nFull output:\n{}",
+        output
+    );
+    assert!(
+        output.lines().count() <= MAX_DEFAULT_LINES,
+        "Too many lines at the default verbosity\nFull output:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_api_dep_stats() {
+    let output = adg_basic_output();
+    assert!(
+        output.contains("API Graph Statistics:") && output.contains("hub types"),
+        "Missing API graph statistics\nFull output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("API nodes: 5"),
+        "Unexpected API count\nFull output:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_callgraph_const_static() {
+    let output = running_tests_with_arg("callgraph/const_static", "-callgraph");
+    assert!(
+        output.contains("SIDE calls:") && output.contains("AREA calls:"),
+        "Missing calls in const and static initializers\nFull output:\n{}",
+        output
+    );
+    assert!(output.contains("cube calls:"));
+
+    // the callees of a closure are found in the closure's own body
+    let closure_callees: Vec<&str> = output
+        .lines()
+        .skip_while(|line| !line.contains("main::{closure#0} calls:"))
+        .skip(1)
+        .take_while(|line| line.trim_start().starts_with("->"))
+        .collect();
+    assert!(
+        closure_callees.iter().any(|line| line.contains("-> cube")),
+        "Missing callees of the closure\nFull output:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_callgraph_flamegraph() {
+    callgraph_fn_ptr_output();
+    let content = std::fs::read_to_string("./tests/callgraph/fn_ptr/callgraph.folded").unwrap();
+    let stacks: Vec<&str> = content.lines().collect();
+    assert!(stacks.contains(&"main;double 1"));
+    assert!(stacks.contains(&"main;triple 1"));
+    assert!(stacks.contains(&"main;main::{closure#0} 1"));
+}
+
+#[test]
+fn test_callgraph_stats() {
+    let output = running_tests_with_arg("callgraph/recursion", "-callgraph-stats");
+    let stat = |name: &str| -> usize {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(name)?.trim().parse().ok())
+            .unwrap_or_else(|| panic!("Missing '{}'\nFull output:\n{}", name, output))
+    };
+    // `factorial` calls itself, and no functions call each other
+    assert_eq!(stat("recursive SCCs:"), 0);
+    assert_eq!(stat("self-recursive functions:"), 1);
+    assert_eq!(stat("1:"), stat("functions:"));
+}
+
+#[test]
+fn test_callgraph_flamegraph_mutual_recursion() {
+    running_tests_with_arg(
+        "callgraph/mutual_recursion",
+        "-callgraph-flamegraph=callgraph.folded",
+    );
+    let content =
+        std::fs::read_to_string("./tests/callgraph/mutual_recursion/callgraph.folded").unwrap();
+    // `ping` and `pong` are only called by each other, so the chain starts at `ping`
+    let stacks: Vec<&str> = content.lines().collect();
+    assert_eq!(stacks, vec!["ping;pong 1"]);
+}